@@ -0,0 +1,99 @@
+//! Compares decoding responses through an intermediate `serde_json::Value`,
+//! as the client used to, with decoding them straight from the body.
+//!
+//! Run with `cargo bench`. Reports wall time and heap allocations per decode.
+extern crate authy;
+extern crate serde;
+extern crate serde_json;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use authy::{Client, Status, Empty};
+use authy::api::user::UserStatus;
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const ITERATIONS: u32 = 20_000;
+
+const VERIFY: &str = include_str!("../tests/fixtures/verify.json");
+const USER_STATUS: &str = include_str!("../tests/fixtures/user_status.json");
+
+/// The decoding the client did before reading responses straight into types.
+fn via_value(body: &str) -> (Status, Value) {
+    let mut value: Value = serde_json::from_str(body).unwrap();
+    value["success"] = match value.clone()["success"] {
+        Value::Bool(v) => Value::Bool(v),
+        Value::String(ref v) => Value::Bool(v == "true"),
+        _ => Value::Bool(false),
+    };
+    let status: Status = serde_json::from_value(value.clone()).unwrap();
+
+    (status, value)
+}
+
+fn via_value_key<T: DeserializeOwned>(body: &str, key: &str) -> (Status, T) {
+    let (status, value) = via_value(body);
+
+    (status, serde_json::from_value(value[key].clone()).unwrap())
+}
+
+fn bench<F: FnMut()>(name: &str, mut f: F) {
+    f();
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = BYTES.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    let bytes = BYTES.load(Ordering::Relaxed) - bytes;
+
+    println!("{:<24} {:>8} ns/iter {:>6} allocs/iter {:>8} bytes/iter",
+             name,
+             elapsed.as_nanos() / u128::from(ITERATIONS),
+             allocations / ITERATIONS as usize,
+             bytes / ITERATIONS as usize);
+}
+
+fn main() {
+    let c = Client::new("https://sandbox-api.authy.com", "bf12974d70818a08199d17d5e2bae630");
+
+    bench("verify/value", || {
+        via_value(VERIFY);
+    });
+    bench("verify/typed", || {
+        c.decode::<Empty>(VERIFY.as_bytes(), None).unwrap();
+    });
+
+    bench("user_status/value", || {
+        via_value_key::<UserStatus>(USER_STATUS, "status");
+    });
+    bench("user_status/typed", || {
+        c.decode::<UserStatus>(USER_STATUS.as_bytes(), Some("status")).unwrap();
+    });
+}
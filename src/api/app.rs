@@ -2,34 +2,73 @@
 //!
 //! Much of the documentation for this module comes from the Authy TOTP service
 //! documentation.
-use serde_json;
+use std::collections::HashMap;
+
+use serde_json::Value;
 
 use error::AuthyError;
 use client::{Client, Status};
+use de;
+use response::Response;
+use endpoint::{Endpoint, Method};
 
-const PREFIX: &'static str = "protected";
+const PREFIX: &str = "protected";
 
 /// returned when requesting the application details
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Details {
+    #[serde(deserialize_with = "de::u32")]
     pub app_id: u32,
     pub name: String,
+    #[serde(deserialize_with = "de::bool")]
     pub onetouch_enabled: bool,
     pub plan: String,
+    #[serde(deserialize_with = "de::bool")]
     pub sms_enabled: bool,
+
+    /// Any fields returned by Authy that aren't covered above.
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl Response for Details {
+    const REQUIRED: &'static [&'static str] = &["app_id"];
 }
 
 /// returned when requesting the application stats.
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Stats {
     pub month: String,
+    #[serde(deserialize_with = "de::u16")]
     pub year: u16,
 
+    #[serde(deserialize_with = "de::u32")]
     pub api_calls_count: u32,
+    #[serde(deserialize_with = "de::u32")]
     pub auths_count: u32,
+    #[serde(deserialize_with = "de::u32")]
     pub calls_count: u32,
+    #[serde(deserialize_with = "de::u32")]
     pub sms_count: u32,
+    #[serde(deserialize_with = "de::u32")]
     pub users_count: u32,
+
+    /// Any fields returned by Authy that aren't covered above.
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl Response for Stats {}
+
+struct GetDetails;
+
+impl Endpoint for GetDetails {
+    type Response = Details;
+
+    fn method(&self) -> Method { Method::GET }
+    fn prefix(&self) -> &str { PREFIX }
+    fn path(&self) -> String { "app/details".into() }
+    fn key(&self) -> Option<&str> { Some("app") }
 }
 
 /// Get the details for an Authy application.
@@ -37,11 +76,18 @@ pub struct Stats {
 /// Please see the Authy documentation for more details:
 /// https://www.twilio.com/docs/api/authy/authy-totp#application-details
 pub fn details(client: &Client) -> Result<(Status, Details), AuthyError> {
-    let (status, res) = client.get(PREFIX, "app/details", None)?;
+    client.execute(&GetDetails)
+}
 
-    let details = serde_json::from_value(res["app"].clone())?;
+struct GetStats;
 
-    Ok((status, details))
+impl Endpoint for GetStats {
+    type Response = Vec<Stats>;
+
+    fn method(&self) -> Method { Method::GET }
+    fn prefix(&self) -> &str { PREFIX }
+    fn path(&self) -> String { "app/stats".into() }
+    fn key(&self) -> Option<&str> { Some("stats") }
 }
 
 /// Get stats for an Authy application.
@@ -49,9 +95,5 @@ pub fn details(client: &Client) -> Result<(Status, Details), AuthyError> {
 /// Please see the Authy documentation for more details:
 /// https://www.twilio.com/docs/api/authy/authy-totp#application-stats
 pub fn stats(client: &Client) -> Result<(Status, Vec<Stats>), AuthyError> {
-    let (status, res) = client.get(PREFIX, "app/stats", None)?;
-
-    let stats = serde_json::from_value(res["stats"].clone())?;
-
-    Ok((status, stats))
+    client.execute(&GetStats)
 }
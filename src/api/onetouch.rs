@@ -1,46 +1,385 @@
-use serde_json;
+//! Bindings to the [Authy OneTouch api](https://www.twilio.com/docs/authy/api/push-authentications).
+//!
+//! Much of the documentation for this module comes from the Authy OneTouch
+//! service documentation.
 use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+use std::time::Duration;
+
+use reqwest::Url;
+use serde_json::Value;
 
 use error::AuthyError;
 use client::{Client, Status};
+use de;
+use response::Response;
+use endpoint::{Endpoint, Method};
+use form::{Form, Param};
+
+const PREFIX: &str = "onetouch";
+
+/// The longest message Authy will show for an approval request.
+pub const MAX_MESSAGE_LENGTH: usize = 144;
+
+/// The state of an approval request.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApprovalStatus {
+    #[default]
+    Pending,
+    Approved,
+    Denied,
+    Expired,
+
+    /// A status this library doesn't know about yet.
+    #[serde(other)]
+    Unknown,
+}
+
+impl Display for ApprovalStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ApprovalStatus::Pending => write!(f, "pending"),
+            ApprovalStatus::Approved => write!(f, "approved"),
+            ApprovalStatus::Denied => write!(f, "denied"),
+            ApprovalStatus::Expired => write!(f, "expired"),
+            ApprovalStatus::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// The device a user responded to an approval request from.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ApprovalDevice {
+    #[serde(default, deserialize_with = "de::option_u64")]
+    pub id: Option<u64>,
+    pub os_type: Option<String>,
 
-const PREFIX: &'static str = "onetouch";
+    /// Where the response was sent from.
+    pub ip: Option<String>,
+    pub city: Option<String>,
+    pub region: Option<String>,
+    pub country: Option<String>,
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+    /// Where the device was registered with Authy.
+    pub registration_method: Option<String>,
+    #[serde(default, deserialize_with = "de::option_u64")]
+    pub registration_date: Option<u64>,
+    pub registration_ip: Option<String>,
+    pub registration_city: Option<String>,
+    pub registration_region: Option<String>,
+    pub registration_country: Option<String>,
+
+    #[serde(default, deserialize_with = "de::option_u64")]
+    pub last_sync_date: Option<u64>,
+    #[serde(default, deserialize_with = "de::option_u64")]
+    pub last_account_recovery_at: Option<u64>,
+
+    /// Any fields returned by Authy that aren't covered above.
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// The request as it was shown to the user.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ApprovalTransaction {
+    #[serde(default)]
+    pub message: String,
+    pub reason: Option<String>,
+    #[serde(default)]
+    pub details: HashMap<String, String>,
+    #[serde(default)]
+    pub hidden_details: HashMap<String, String>,
+
+    pub status: Option<ApprovalStatus>,
+    pub uuid: Option<String>,
+
+    /// Unix timestamp of when the request was created.
+    #[serde(default, deserialize_with = "de::option_u64")]
+    pub created_at_time: Option<u64>,
+
+    /// Any fields returned by Authy that aren't covered above.
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// Returned when creating an approval request or requesting its status.
+///
+/// Only `uuid` is returned when the request is created, everything else is
+/// filled in by `status`.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
 pub struct ApprovalRequest {
-    uuid: String,
+    /// Identifies the request when requesting its status.
+    pub uuid: String,
+
+    pub status: Option<ApprovalStatus>,
+
+    /// The authy id of the user the request was sent to.
+    #[serde(rename = "_authy_id", default, deserialize_with = "de::option_u32")]
+    pub authy_id: Option<u32>,
+
+    /// Whether a push notification was delivered to the user's device.
+    #[serde(default, deserialize_with = "de::option_bool")]
+    pub notified: Option<bool>,
+
+    /// Seconds the request was valid for when it was created.
+    #[serde(default, deserialize_with = "de::option_u32")]
+    pub seconds_to_expire: Option<u32>,
+
+    /// ISO 8601 timestamps of the request's lifecycle.
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub processed_at: Option<String>,
+
+    /// The device the user responded from, once they have.
+    pub device: Option<ApprovalDevice>,
+
+    /// The request as it was sent, when Authy includes it.
+    pub transaction: Option<ApprovalTransaction>,
+
+    /// Any fields returned by Authy that aren't covered above.
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
-pub fn request(client: &Client, id: u32, message: &str, details: Option<&HashMap<&str, String>>, hidden_details: Option<&HashMap<&str, String>>, logos: Option<&HashMap<&str, String>>, seconds_to_expire: Option<u32>) -> Result<(Status, ApprovalRequest), AuthyError> {
-    let mut params: Vec<(String, String)> = vec![];
-    params.push(("message".into(), message.into()));
+impl Response for ApprovalRequest {
+    const REQUIRED: &'static [&'static str] = &["uuid"];
+}
+
+/// The resolution a logo is intended for.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum LogoResolution {
+    Default,
+    Low,
+    Med,
+    High,
+}
 
-    if let Some(seconds_to_expire) = seconds_to_expire {
-        params.push(("seconds_to_expire".into(), seconds_to_expire.to_string()));
+impl Display for LogoResolution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LogoResolution::Default => write!(f, "default"),
+            LogoResolution::Low => write!(f, "low"),
+            LogoResolution::Med => write!(f, "med"),
+            LogoResolution::High => write!(f, "high"),
+        }
     }
+}
 
-    if let Some(details) = details {
-        for (k, v) in details {
-            params.push((format!("details[{}]", k), v.clone()));
+impl FromStr for LogoResolution {
+    type Err = AuthyError;
+
+    fn from_str(s: &str) -> Result<LogoResolution, AuthyError> {
+        match s {
+            "default" => Ok(LogoResolution::Default),
+            "low" => Ok(LogoResolution::Low),
+            "med" => Ok(LogoResolution::Med),
+            "high" => Ok(LogoResolution::High),
+            other => Err(AuthyError::InvalidRequest(format!("Unknown logo resolution: {}", other))),
         }
     }
+}
 
-    if let Some(hidden_details) = hidden_details {
-        for (k, v) in hidden_details {
-            params.push((format!("hidden_details[{}]", k), v.clone()));
+/// A logo shown to the user along with an approval request.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Logo {
+    pub res: LogoResolution,
+    pub url: String,
+}
+
+/// Describes a OneTouch approval request before it is sent with `create`.
+///
+/// Details are shown to the user in the order they were added. Hidden details
+/// are stored with the request but are not shown to the user.
+///
+/// Example:
+///
+/// ```rust,ignore
+/// let request = ApprovalRequestBuilder::new("Login requested for a CapTrade Bank account.")
+///     .detail("Username", "Bill Smith")
+///     .detail("Location", "California, USA")
+///     .hidden_detail("ip_address", "10.10.3.203")
+///     .logo(LogoResolution::Default, "https://example.com/logo.png")
+///     .expires_in(Duration::from_secs(120));
+///
+/// let (status, approval_request) = onetouch::create(&c, user.id, &request)?;
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ApprovalRequestBuilder {
+    message: String,
+    details: Vec<(String, String)>,
+    hidden_details: Vec<(String, String)>,
+    logos: Vec<Logo>,
+    expires_in: Option<Duration>,
+    reason: Option<String>,
+}
+
+impl ApprovalRequestBuilder {
+    /// Start an approval request showing the user `message`.
+    pub fn new(message: &str) -> ApprovalRequestBuilder {
+        ApprovalRequestBuilder {
+            message: message.into(),
+            ..ApprovalRequestBuilder::default()
         }
     }
-    
-    if let Some(logos) = logos {
-        for (k, v) in logos {
-            params.push(("logos[][res]".into(), k.clone().into()));
-            params.push(("logos[][res]".into(), v.clone()));
+
+    /// Add a detail shown to the user.
+    pub fn detail(mut self, key: &str, value: &str) -> ApprovalRequestBuilder {
+        self.details.push((key.into(), value.into()));
+        self
+    }
+
+    /// Add a detail that is stored with the request but not shown to the user.
+    pub fn hidden_detail(mut self, key: &str, value: &str) -> ApprovalRequestBuilder {
+        self.hidden_details.push((key.into(), value.into()));
+        self
+    }
+
+    /// Add a logo to show in place of the application's default logo.
+    pub fn logo(mut self, res: LogoResolution, url: &str) -> ApprovalRequestBuilder {
+        self.logos.push(Logo { res, url: url.into() });
+        self
+    }
+
+    /// How long the user has to respond, in whole seconds. Authy uses one day
+    /// when this isn't given, and a duration of zero means the request never
+    /// expires.
+    pub fn expires_in(mut self, expires_in: Duration) -> ApprovalRequestBuilder {
+        self.expires_in = Some(expires_in);
+        self
+    }
+
+    /// The reason for the request, such as "Login" or "Transfer".
+    pub fn reason(mut self, reason: &str) -> ApprovalRequestBuilder {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    /// Check the request against Authy's limits without sending it.
+    pub fn validate(&self) -> Result<(), AuthyError> {
+        if self.message.trim().is_empty() {
+            return Err(AuthyError::InvalidRequest("Approval request message is empty".into()));
         }
+        if self.message.chars().count() > MAX_MESSAGE_LENGTH {
+            return Err(AuthyError::InvalidRequest(format!("Approval request message is longer than {} characters", MAX_MESSAGE_LENGTH)));
+        }
+
+        // Sent in whole seconds, so anything shorter would become zero and
+        // never expire.
+        if let Some(d) = self.expires_in {
+            if d > Duration::from_secs(0) && d < Duration::from_secs(1) {
+                return Err(AuthyError::InvalidRequest("Approval request must expire in at least 1 second".into()));
+            }
+        }
+
+        for &(name, details) in &[("details", &self.details), ("hidden_details", &self.hidden_details)] {
+            for (i, (key, _)) in details.iter().enumerate() {
+                if key.trim().is_empty() {
+                    return Err(AuthyError::InvalidRequest(format!("Empty key in {}", name)));
+                }
+                if details[..i].iter().any(|(k, _)| k == key) {
+                    return Err(AuthyError::InvalidRequest(format!("Duplicate key in {}: {}", name, key)));
+                }
+            }
+        }
+
+        for (i, logo) in self.logos.iter().enumerate() {
+            if self.logos[..i].iter().any(|l| l.res == logo.res) {
+                return Err(AuthyError::InvalidRequest(format!("More than one {} logo", logo.res)));
+            }
+            if Url::parse(&logo.url).is_err() {
+                return Err(AuthyError::InvalidRequest(format!("Invalid logo url: {}", logo.url)));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn form(&self) -> Form {
+        let mut form = Form::new();
+        form.field("message", &self.message)
+            .optional("seconds_to_expire", self.expires_in.map(|d| d.as_secs()))
+            .optional("reason", self.reason.as_ref());
+
+        form.map("details", self.details.iter().map(|(k, v)| (k, v)))
+            .map("hidden_details", self.hidden_details.iter().map(|(k, v)| (k, v)))
+            .nested("logos", Param::list(self.logos.iter().map(|logo| {
+                Param::map(vec![("res", logo.res.to_string()), ("url", logo.url.clone())])
+            })));
+        form
+    }
+}
+
+struct Create {
+    id: u32,
+    form: Form,
+}
+
+impl Endpoint for Create {
+    type Response = ApprovalRequest;
+
+    fn method(&self) -> Method { Method::POST }
+    fn prefix(&self) -> &str { PREFIX }
+    fn path(&self) -> String { format!("users/{}/approval_requests", self.id) }
+    fn key(&self) -> Option<&str> { Some("approval_request") }
+
+    fn form(&self) -> Vec<(String, String)> {
+        self.form.clone().into()
     }
+}
+
+/// Send a OneTouch approval request to a user.
+///
+/// The request is validated before it is sent, returning
+/// `AuthyError::InvalidRequest` when it falls outside of Authy's limits.
+///
+/// Please see the Authy documentation for more details:
+/// https://www.twilio.com/docs/authy/api/push-authentications#create-an-approval-request
+pub fn create(client: &Client, id: u32, request: &ApprovalRequestBuilder) -> Result<(Status, ApprovalRequest), AuthyError> {
+    request.validate()?;
 
-    let (status, res) = client.post(PREFIX, &format!("users/{}/approval_requests", id), None, Some(params))?;
+    client.execute(&Create { id, form: request.form() })
+}
+
+/// Please use `create`.
+///
+/// The request is sent as given, without the validation `create` does.
+#[deprecated(note = "use `create` with an `ApprovalRequestBuilder`")]
+pub fn request(client: &Client, id: u32, message: &str, details: Option<&HashMap<&str, String>>, hidden_details: Option<&HashMap<&str, String>>, logos: Option<&HashMap<&str, String>>, seconds_to_expire: Option<u32>) -> Result<(Status, ApprovalRequest), AuthyError> {
+    let mut form = Form::new();
+    form.field("message", message)
+        .optional("seconds_to_expire", seconds_to_expire);
+
+    form.map("details", details.into_iter().flatten())
+        .map("hidden_details", hidden_details.into_iter().flatten())
+        .nested("logos", Param::list(logos.into_iter().flatten().map(|(res, url)| {
+            Param::map(vec![("res", res.to_string()), ("url", url.clone())])
+        })));
+
+    client.execute(&Create { id, form })
+}
+
+struct GetStatus<'a> {
+    uuid: &'a str,
+}
 
-    let approval_request = serde_json::from_value(res["approval_request"].clone())?;
+impl<'a> Endpoint for GetStatus<'a> {
+    type Response = ApprovalRequest;
+
+    fn method(&self) -> Method { Method::GET }
+    fn prefix(&self) -> &str { PREFIX }
+    fn path(&self) -> String { format!("approval_requests/{}", self.uuid) }
+    fn key(&self) -> Option<&str> { Some("approval_request") }
+}
 
-    Ok((status, approval_request))
+/// Status of an approval request.
+///
+/// Returns the approval request identified by `uuid`, including whether the
+/// user has approved or denied it, or whether it has expired.
+///
+/// Please see the Authy documentation for more details:
+/// https://www.twilio.com/docs/authy/api/push-authentications#check-approval-request-status
+pub fn status(client: &Client, uuid: &str) -> Result<(Status, ApprovalRequest), AuthyError> {
+    client.execute(&GetStatus { uuid })
 }
@@ -2,17 +2,23 @@
 //!
 //! Much of the documentation for this module comes from the Authy TOTP service
 //! documentation.
+use std::collections::HashMap;
 use std::fmt::{self, Display};
 
-use serde_json;
+use serde_json::Value;
 
 use error::AuthyError;
 use client::{Client, Status};
+use de;
+use response::{Response, Empty};
+use endpoint::{Endpoint, Method};
+use form::Form;
+use locale::Locale;
 
-const PREFIX: &'static str = "protected";
+const PREFIX: &str = "protected";
 
 /// Returned when requesting info on a phone number
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
 pub struct PhoneInfo {
     /// Phone number type. It can be voip, landline, cellphone, unknown.
     #[serde(rename = "type")]
@@ -22,22 +28,106 @@ pub struct PhoneInfo {
     pub provider: Option<String>,
 
     /// Whether the phone number was ported or not.
+    #[serde(deserialize_with = "de::bool")]
     pub ported: bool,
+
+    /// Any fields returned by Authy that aren't covered above.
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl Response for PhoneInfo {
+    const REQUIRED: &'static [&'static str] = &["type"];
 }
 
 /// Returned when initiating verification of a phone number.
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
 pub struct PhoneStart {
     pub carrier: String,
+    #[serde(deserialize_with = "de::bool")]
     pub is_cellphone: bool,
     pub message: String,
+    #[serde(deserialize_with = "de::u16")]
     pub seconds_to_expire: u16,
     pub uuid: Option<String>,
+    #[serde(deserialize_with = "de::bool")]
     pub success: bool,
+
+    /// Any fields returned by Authy that aren't covered above.
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl Response for PhoneStart {}
+
+/// The state of a phone verification.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerificationStatus {
+    /// A code was sent and hasn't been checked yet.
+    #[default]
+    Pending,
+
+    /// The code was checked successfully.
+    Verified,
+
+    /// The code expired before it was checked.
+    Expired,
+
+    /// A status this library doesn't know about yet.
+    #[serde(other)]
+    Unknown,
+}
+
+impl Display for VerificationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VerificationStatus::Pending => write!(f, "pending"),
+            VerificationStatus::Verified => write!(f, "verified"),
+            VerificationStatus::Expired => write!(f, "expired"),
+            VerificationStatus::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// Returned when requesting the status of a phone verification.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PhoneVerificationStatus {
+    pub status: VerificationStatus,
+
+    /// Seconds until the code expires, zero once it has.
+    #[serde(default, deserialize_with = "de::u32")]
+    pub seconds_to_expire: u32,
+
+    /// Any fields returned by Authy that aren't covered above.
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl Response for PhoneVerificationStatus {
+    const REQUIRED: &'static [&'static str] = &["status"];
+}
+
+/// Identifies a phone verification when requesting its status.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Verification<'a> {
+    /// The `uuid` returned by `start`.
+    Uuid(&'a str),
+
+    /// The latest verification started for a phone number.
+    Number { country_code: u16, phone: &'a str },
 }
 
+/// The error code Authy returns when a verification code is incorrect.
+pub const ERROR_WRONG_CODE: &str = "60022";
+
+/// The error code Authy returns when there is no pending verification for a
+/// phone number, such as when it has expired or was already checked.
+pub const ERROR_NO_PENDING_VERIFICATION: &str = "60023";
+
 /// The contact type used when verifying a phone number
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ContactType {
     SMS,
     Call,
@@ -52,6 +142,28 @@ impl Display for ContactType {
     }
 }
 
+struct Info<'a> {
+    country_code: u16,
+    phone: &'a str,
+    user_ip: Option<&'a str>,
+}
+
+impl<'a> Endpoint for Info<'a> {
+    type Response = PhoneInfo;
+
+    fn method(&self) -> Method { Method::GET }
+    fn prefix(&self) -> &str { PREFIX }
+    fn path(&self) -> String { "phones/info".into() }
+
+    fn query(&self) -> Vec<(String, String)> {
+        let mut form = Form::new();
+        form.field("country_code", self.country_code)
+            .field("phone_number", self.phone)
+            .optional("user_ip", self.user_ip);
+        form.into()
+    }
+}
+
 /// Request information on a phone number.
 ///
 /// The Authy Phone Intelligence API provides information about a phone number.
@@ -64,18 +176,156 @@ impl Display for ContactType {
 /// Please see the Authy documentation for more details:
 /// https://www.twilio.com/docs/api/authy/authy-phone-intelligence-api
 pub fn info(client: &Client, country_code: u16, phone: &str, user_ip: Option<&str>) -> Result<(Status, PhoneInfo), AuthyError> {
-    let mut params: Vec<(String, String)> = vec![];
-    params.push(("country_code".into(), country_code.to_string()));
-    params.push(("phone_number".into(), phone.into()));
-    if let Some(user_ip) = user_ip {
-        params.push(("user_ip".into(), user_ip.into()));
-    };
+    client.execute(&Info { country_code, phone, user_ip })
+}
+
+/// The shortest verification code Authy will send.
+pub const MIN_CODE_LENGTH: u8 = 4;
+
+/// The longest verification code Authy will send.
+pub const MAX_CODE_LENGTH: u8 = 10;
+
+/// The length of the app hash used by Android's SMS Retriever API.
+pub const APP_HASH_LENGTH: usize = 11;
+
+/// Optional parameters for `start`.
+///
+/// Example:
+///
+/// ```rust,ignore
+/// let options = PhoneStartOptions::new()
+///     .code_length(6)
+///     .locale(Locale::PortugueseBR)
+///     .app_hash("FA+9qCX9VSu");
+///
+/// let (status, phone_start) = phone::start(&c, ContactType::SMS, 55, "11-5555-1234", &options)?;
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct PhoneStartOptions {
+    code_length: Option<u8>,
+    locale: Option<Locale>,
+    infer_locale: bool,
+    custom_code: Option<String>,
+    custom_message: Option<String>,
+    app_hash: Option<String>,
+}
+
+impl PhoneStartOptions {
+    pub fn new() -> PhoneStartOptions {
+        PhoneStartOptions::default()
+    }
 
-    let (status, res) = client.get(PREFIX, "phones/info", Some(params))?;
+    /// Number of digits in the code, between 4 and 10. Authy sends 4 when
+    /// this isn't given.
+    pub fn code_length(mut self, code_length: u8) -> PhoneStartOptions {
+        self.code_length = Some(code_length);
+        self
+    }
+
+    /// The language of the message.
+    pub fn locale(mut self, locale: Locale) -> PhoneStartOptions {
+        self.locale = Some(locale);
+        self
+    }
+
+    /// When no locale is given, use the language most widely spoken for the
+    /// phone's country code, if there is a clear one. Otherwise Authy picks
+    /// the language.
+    pub fn infer_locale(mut self) -> PhoneStartOptions {
+        self.infer_locale = true;
+        self
+    }
+
+    /// The locale that will be sent for a phone with `country_code`.
+    pub fn locale_for(&self, country_code: u16) -> Option<Locale> {
+        match self.locale {
+            Some(locale) => Some(locale),
+            None if self.infer_locale => Locale::from_country_code(country_code),
+            None => None,
+        }
+    }
+
+    /// Send this code instead of one generated by Authy. Your application
+    /// must be enabled for custom codes.
+    pub fn custom_code(mut self, custom_code: &str) -> PhoneStartOptions {
+        self.custom_code = Some(custom_code.into());
+        self
+    }
+
+    /// Send this message in place of Authy's. Your application must be
+    /// enabled for custom messages.
+    pub fn custom_message(mut self, custom_message: &str) -> PhoneStartOptions {
+        self.custom_message = Some(custom_message.into());
+        self
+    }
+
+    /// Append the hash of your Android app to the message, so that the SMS
+    /// Retriever API can read the code without the user typing it in.
+    pub fn app_hash(mut self, app_hash: &str) -> PhoneStartOptions {
+        self.app_hash = Some(app_hash.into());
+        self
+    }
+
+    /// Check the options without sending them.
+    pub fn validate(&self) -> Result<(), AuthyError> {
+        if let Some(code_length) = self.code_length {
+            if !(MIN_CODE_LENGTH..=MAX_CODE_LENGTH).contains(&code_length) {
+                return Err(AuthyError::InvalidRequest(format!("Code length must be between {} and {}", MIN_CODE_LENGTH, MAX_CODE_LENGTH)));
+            }
+        }
+
+        if let Some(ref code) = self.custom_code {
+            if code.is_empty() || !code.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(AuthyError::InvalidRequest(format!("Custom code must be numeric: {}", code)));
+            }
+            let expected = self.code_length.unwrap_or(MIN_CODE_LENGTH);
+            if code.len() != expected as usize {
+                return Err(AuthyError::InvalidRequest(format!("Custom code must be {} digits long", expected)));
+            }
+        }
+
+        if self.custom_message.as_ref().is_some_and(|m| m.trim().is_empty()) {
+            return Err(AuthyError::InvalidRequest("Custom message is empty".into()));
+        }
+
+        if let Some(ref app_hash) = self.app_hash {
+            let valid = app_hash.len() == APP_HASH_LENGTH
+                && app_hash.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'/');
+            if !valid {
+                return Err(AuthyError::InvalidRequest(format!("Invalid app hash: {}", app_hash)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct Start<'a> {
+    via: ContactType,
+    country_code: u16,
+    phone: &'a str,
+    options: &'a PhoneStartOptions,
+}
 
-    let phone_info = serde_json::from_value(res)?;
+impl<'a> Endpoint for Start<'a> {
+    type Response = PhoneStart;
 
-    Ok((status, phone_info))
+    fn method(&self) -> Method { Method::POST }
+    fn prefix(&self) -> &str { PREFIX }
+    fn path(&self) -> String { "phones/verification/start".into() }
+
+    fn form(&self) -> Vec<(String, String)> {
+        let mut form = Form::new();
+        form.field("via", self.via)
+            .field("country_code", self.country_code)
+            .field("phone_number", self.phone)
+            .optional("code_length", self.options.code_length)
+            .optional("locale", self.options.locale_for(self.country_code))
+            .optional("custom_code", self.options.custom_code.as_ref())
+            .optional("custom_message", self.options.custom_message.as_ref())
+            .optional("app_hash", self.options.app_hash.as_ref());
+        form.into()
+    }
 }
 
 /// Initiate a phone verification check.
@@ -90,24 +340,37 @@ pub fn info(client: &Client, country_code: u16, phone: &str, user_ip: Option<&st
 /// valid for 10 minutes. Subsequent calls to the API within the expiration
 /// time will send the same verification code.
 ///
+/// The options are validated before the request is sent, returning
+/// `AuthyError::InvalidRequest` when they fall outside of Authy's limits.
+///
 /// Please see the Authy documentation for more details:
 /// https://www.twilio.com/docs/api/authy/authy-phone-verification-api#requesting-and-verifying-the-verification-code
-pub fn start(client: &Client, via: ContactType, country_code: u16, phone: &str, code_length: Option<u8>, locale: Option<&str>) -> Result<(Status, PhoneStart), AuthyError> {
-    let mut params: Vec<(String, String)> = vec![];
-    params.push(("via".into(), via.to_string()));
-    params.push(("country_code".into(), country_code.to_string()));
-    params.push(("phone_number".into(), phone.into()));
-    if let Some(code_length) = code_length {
-        params.push(("code_length".into(), code_length.to_string()));
-    };
-    if let Some(locale) = locale {
-        params.push(("locale".into(), locale.into()));
-    };
+pub fn start(client: &Client, via: ContactType, country_code: u16, phone: &str, options: &PhoneStartOptions) -> Result<(Status, PhoneStart), AuthyError> {
+    options.validate()?;
 
-    let (status, res) = client.post(PREFIX, "phones/verification/start", None, Some(params))?;
-    let phone_verification = serde_json::from_value(res)?;
+    client.execute(&Start { via, country_code, phone, options })
+}
 
-    Ok((status, phone_verification))
+struct Check<'a> {
+    country_code: u16,
+    phone: &'a str,
+    code: &'a str,
+}
+
+impl<'a> Endpoint for Check<'a> {
+    type Response = Empty;
+
+    fn method(&self) -> Method { Method::GET }
+    fn prefix(&self) -> &str { PREFIX }
+    fn path(&self) -> String { "phones/verification/check".into() }
+
+    fn query(&self) -> Vec<(String, String)> {
+        let mut form = Form::new();
+        form.field("country_code", self.country_code)
+            .field("phone_number", self.phone)
+            .field("verification_code", self.code);
+        form.into()
+    }
 }
 
 /// Verify phone verification code sent to user.
@@ -115,12 +378,43 @@ pub fn start(client: &Client, via: ContactType, country_code: u16, phone: &str,
 /// Please see the Authy documentation for more details:
 /// https://www.twilio.com/docs/api/authy/authy-phone-verification-api#verifying-code-sent-to-the-user
 pub fn check(client: &Client, country_code: u16, phone: &str, code: &str) -> Result<Status, AuthyError> {
-    let mut params: Vec<(String, String)> = vec![];
-    params.push(("country_code".into(), country_code.to_string()));
-    params.push(("phone_number".into(), phone.into()));
-    params.push(("verification_code".into(), code.into()));
-
-    let (status, _) = client.get(PREFIX, "phones/verification/check", Some(params))?;
+    let (status, Empty) = client.execute(&Check { country_code, phone, code })?;
 
     Ok(status)
 }
+
+struct GetStatus<'a> {
+    verification: Verification<'a>,
+}
+
+impl<'a> Endpoint for GetStatus<'a> {
+    type Response = PhoneVerificationStatus;
+
+    fn method(&self) -> Method { Method::GET }
+    fn prefix(&self) -> &str { PREFIX }
+    fn path(&self) -> String { "phones/verification/status".into() }
+
+    fn query(&self) -> Vec<(String, String)> {
+        let mut form = Form::new();
+        match self.verification {
+            Verification::Uuid(uuid) => { form.field("uuid", uuid); },
+            Verification::Number { country_code, phone } => {
+                form.field("country_code", country_code)
+                    .field("phone_number", phone);
+            },
+        }
+        form.into()
+    }
+}
+
+/// Status of a phone verification.
+///
+/// Returns whether the verification is still pending, was verified or has
+/// expired, looked up either by the `uuid` returned from `start` or by the
+/// phone number.
+///
+/// Please see the Authy documentation for more details:
+/// https://www.twilio.com/docs/authy/api/phone-verification#check-the-verification-status
+pub fn status(client: &Client, verification: Verification) -> Result<(Status, PhoneVerificationStatus), AuthyError> {
+    client.execute(&GetStatus { verification })
+}
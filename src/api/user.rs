@@ -4,52 +4,80 @@
 //! documentation.
 use std::fmt::{self, Display};
 
-use serde_json;
+use serde_json::Value;
 use std::collections::HashMap;
 
 use error::AuthyError;
 use client::{Client, Status};
+use de;
+use response::{Response, Empty};
+use endpoint::{Endpoint, Method};
+use form::Form;
+use locale::Locale;
 
-const PREFIX: &'static str = "protected";
+const PREFIX: &str = "protected";
 
 /// Returned when creating a new authy user. 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
 pub struct UserNew {
 /// This id is unique per API KEY and should be stored in your database.
+    #[serde(deserialize_with = "de::u32")]
     pub id: u32,
+
+    /// Any fields returned by Authy that aren't covered above.
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl Response for UserNew {
+    const REQUIRED: &'static [&'static str] = &["id"];
 }
 
 /// Returned when requesting the status of an authy user.
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
 pub struct UserStatus {
     /// The authy id for the user.
-    #[serde(rename = "authy_id")]
+    #[serde(rename = "authy_id", deserialize_with = "de::u32")]
     pub id: u32,
 
     /// true when the user has used a valid code before.
+    #[serde(deserialize_with = "de::bool")]
     pub confirmed: bool,
 
     /// true when the Authy Mobile/Desktop App was registered.
+    #[serde(deserialize_with = "de::bool")]
     pub registered: bool,
     
     /// Has the account been marked for deletion
+    #[serde(deserialize_with = "de::bool")]
     pub account_disabled: bool,
 
     /// The country code listed for the user.
+    #[serde(deserialize_with = "de::u16")]
     pub country_code: u16,
     
     /// The last 4 of the phone number registered to the account.
     pub phone_number: String,
 
     /// (Unknown, API documentation doesn't list)
+    #[serde(deserialize_with = "de::bool")]
     pub has_hard_token: bool,
 
     /// List of devices, options are: android, android_tablet, ios, authy_chrome, sms.
+    #[serde(deserialize_with = "de::vec")]
     pub devices: Vec<String>,
+
+    /// Any fields returned by Authy that aren't covered above.
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl Response for UserStatus {
+    const REQUIRED: &'static [&'static str] = &["authy_id"];
 }
 
 /// Returned when sending a verification code to a user via SMS or Call.
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
 pub struct PhoneCall {
     /// The phone number used to send the message.
     pub cellphone: String,
@@ -59,7 +87,38 @@ pub struct PhoneCall {
     pub device: Option<String>,
 
     /// True if the request was ignored.
+    #[serde(default, deserialize_with = "de::option_bool")]
     pub ignored: Option<bool>,
+
+    /// Any fields returned by Authy that aren't covered above.
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl Response for PhoneCall {}
+
+/// Returned when generating a QR code for a TOTP authenticator app.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct QrCode {
+    /// Url of the QR code image.
+    pub qr_code: String,
+
+    /// The account name shown in the authenticator app.
+    pub label: Option<String>,
+
+    /// The issuer shown in the authenticator app.
+    pub issuer: Option<String>,
+
+    /// The shared secret encoded in the QR code, when Authy returns it.
+    pub secret: Option<String>,
+
+    /// Any fields returned by Authy that aren't covered above.
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl Response for QrCode {
+    const REQUIRED: &'static [&'static str] = &["qr_code"];
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -87,6 +146,35 @@ pub fn new(client: &Client, email: &str, country_code: u16, phone: &str, send_in
     create(client, email, country_code, phone, send_instructions)
 }
 
+struct Create<'a> {
+    email: &'a str,
+    country_code: u16,
+    phone: &'a str,
+    send_instructions: bool,
+}
+
+impl<'a> Endpoint for Create<'a> {
+    type Response = UserNew;
+
+    fn method(&self) -> Method { Method::POST }
+    fn prefix(&self) -> &str { PREFIX }
+    fn path(&self) -> String { "users/new".into() }
+    fn key(&self) -> Option<&str> { Some("user") }
+
+    fn form(&self) -> Vec<(String, String)> {
+        let mut form = Form::new();
+        form.map("user", vec![
+            ("email", self.email.to_string()),
+            ("cellphone", self.phone.to_string()),
+            ("country_code", self.country_code.to_string()),
+        ]);
+        if self.send_instructions {
+            form.field("send_install_link_via_sms", true);
+        }
+        form.into()
+    }
+}
+
 /// Creates a new Authy user.
 ///
 /// Before you can secure a user's login you need to create an Authy user. 
@@ -117,19 +205,19 @@ pub fn new(client: &Client, email: &str, country_code: u16, phone: &str, send_in
 /// println!("My new authy user is: {}", user.id);
 /// ```
 pub fn create(client: &Client, email: &str, country_code: u16, phone: &str, send_instructions: bool) -> Result<(Status, UserNew), AuthyError> {
-    let mut params: Vec<(String, String)> = vec![];
-    params.push(("user[email]".into(), email.into()));
-    params.push(("user[cellphone]".into(), phone.into()));
-    params.push(("user[country_code]".into(), country_code.to_string()));
-    if send_instructions {
-        params.push(("send_install_link_via_sms".into(), "true".into()));
-    }
+    client.execute(&Create { email, country_code, phone, send_instructions })
+}
 
-    let (status, res) = client.post(PREFIX, "users/new", None, Some(params))?;
+struct Delete {
+    id: u32,
+}
 
-    let user = serde_json::from_value(res["user"].clone())?;
+impl Endpoint for Delete {
+    type Response = Empty;
 
-    Ok((status, user))
+    fn method(&self) -> Method { Method::POST }
+    fn prefix(&self) -> &str { PREFIX }
+    fn path(&self) -> String { format!("users/{}/delete", self.id) }
 }
 
 /// Deletes an Authy user.
@@ -145,11 +233,24 @@ pub fn create(client: &Client, email: &str, country_code: u16, phone: &str, send
 /// Please see the Authy documentation for more details:
 /// https://www.twilio.com/docs/api/authy/authy-totp#deleting-user
 pub fn delete(client: &Client, id: u32) -> Result<Status, AuthyError> {
-    let (status, _) = client.post(PREFIX, &format!("users/{}/delete", id), None, None)?;
+    let (status, Empty) = client.execute(&Delete { id })?;
 
     Ok(status)
 }
 
+struct GetStatus {
+    id: u32,
+}
+
+impl Endpoint for GetStatus {
+    type Response = UserStatus;
+
+    fn method(&self) -> Method { Method::GET }
+    fn prefix(&self) -> &str { PREFIX }
+    fn path(&self) -> String { format!("users/{}/status", self.id) }
+    fn key(&self) -> Option<&str> { Some("status") }
+}
+
 /// Status of an Authy user.
 ///
 /// This will return various details of an Authy user such as their 
@@ -159,11 +260,42 @@ pub fn delete(client: &Client, id: u32) -> Result<Status, AuthyError> {
 /// Please see the Authy documentation for more details:
 /// https://www.twilio.com/docs/api/authy/authy-totp#user-status
 pub fn status(client: &Client, id: u32) -> Result<(Status, UserStatus), AuthyError> {
-    let (status, res) = client.get(PREFIX, &format!("users/{}/status", id), None)?;
+    client.execute(&GetStatus { id })
+}
 
-    let user_status = serde_json::from_value(res["status"].clone())?;
+/// Options for verifying a token.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct VerifyOptions<'a> {
+    /// Verify the token even if the user has never confirmed a token before.
+    /// Without this, Authy accepts any token until the user has.
+    pub force: bool,
 
-    Ok((status, user_status))
+    /// The action the token was sent for with `sms` or `call`. Tokens sent
+    /// for an action are only valid when verified with the same action.
+    pub action: Option<&'a str>,
+}
+
+struct Verify<'a> {
+    id: u32,
+    token: &'a str,
+    options: &'a VerifyOptions<'a>,
+}
+
+impl<'a> Endpoint for Verify<'a> {
+    type Response = Empty;
+
+    fn method(&self) -> Method { Method::GET }
+    fn prefix(&self) -> &str { PREFIX }
+    fn path(&self) -> String { format!("verify/{token}/{id}", token = self.token, id = self.id) }
+
+    fn query(&self) -> Vec<(String, String)> {
+        let mut form = Form::new();
+        if self.options.force {
+            form.field("force", true);
+        }
+        form.optional("action", self.options.action);
+        form.into()
+    }
 }
 
 /// Verify an authentication token.
@@ -175,32 +307,76 @@ pub fn status(client: &Client, id: u32) -> Result<(Status, UserStatus), AuthyErr
 ///
 /// To prevent user from being locked out, until the user successfully logs 
 /// in once using Authy this call will return 200 (valid token). If you wish
-/// to verify token regardless, see below to see how to force verification. 
+/// to verify token regardless, set `force` in `options`.
 /// HTTP 200 means valid token and HTTP 401 means invalid token
 ///
+/// Tokens sent with an `action` from `sms` or `call` must be verified with
+/// the same `action` in `options`.
+///
 /// Please see the Authy documentation for more details:
 /// https://www.twilio.com/docs/api/authy/authy-totp#verifying-a-token
-pub fn verify(client: &Client, id: u32, token: &str) -> Result<Status, AuthyError> {
-    let (status, _) = client.get(PREFIX, &format!("verify/{token}/{id}", token = token, id = id), None)?;
+pub fn verify(client: &Client, id: u32, token: &str, options: &VerifyOptions) -> Result<Status, AuthyError> {
+    let (status, Empty) = client.execute(&Verify { id, token, options })?;
 
     Ok(status)
 }
 
-fn phone(client: &Client, kind: &str, id: u32, force: bool, action: Option<&str>, action_message: Option<&str>) -> Result<(Status, PhoneCall), AuthyError> {
-    let mut params: Vec<(String, String)> = vec![];
-    params.push(("force".into(), force.to_string()));
-    if let Some(action) = action {
-        params.push(("action".into(), action.into()));
-    }
-    if let Some(action_message) = action_message {
-        params.push(("action_message".into(), action_message.into()));
-    }
+/// Options for sending a token with `sms` or `call`.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct TokenRequestOptions<'a> {
+    /// Send the token even if the user has the Authy app installed.
+    pub force: bool,
+
+    /// Only accept the token when it is verified with the same action.
+    pub action: Option<&'a str>,
 
-    let (status, res) = client.get(PREFIX, &format!("{}/{}", kind, id), Some(params))?;
+    /// A message describing the action, sent along with the token.
+    pub action_message: Option<&'a str>,
 
-    let sms = serde_json::from_value(res)?;
+    /// The language of the message. Authy uses the language of the user's
+    /// country when not given.
+    pub locale: Option<Locale>,
 
-    Ok((status, sms))
+    /// Send this code instead of one generated by Authy. Your application
+    /// must be enabled for custom codes.
+    pub custom_code: Option<&'a str>,
+}
+
+impl<'a> TokenRequestOptions<'a> {
+    /// Check the options without sending them.
+    pub fn validate(&self) -> Result<(), AuthyError> {
+        if let Some(code) = self.custom_code {
+            if code.is_empty() || !code.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(AuthyError::InvalidRequest(format!("Custom code must be numeric: {}", code)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct Token<'a> {
+    kind: &'a str,
+    id: u32,
+    options: &'a TokenRequestOptions<'a>,
+}
+
+impl<'a> Endpoint for Token<'a> {
+    type Response = PhoneCall;
+
+    fn method(&self) -> Method { Method::GET }
+    fn prefix(&self) -> &str { PREFIX }
+    fn path(&self) -> String { format!("{}/{}", self.kind, self.id) }
+
+    fn query(&self) -> Vec<(String, String)> {
+        let mut form = Form::new();
+        form.field("force", self.options.force)
+            .optional("action", self.options.action)
+            .optional("action_message", self.options.action_message)
+            .optional("locale", self.options.locale)
+            .optional("custom_code", self.options.custom_code);
+        form.into()
+    }
 }
 
 /// Send token to user via SMS.
@@ -211,7 +387,8 @@ fn phone(client: &Client, kind: &str, id: u32, force: bool, action: Option<&str>
 /// users that don't own a smartphone, Authy allows you to use text messages
 /// to send the one time passcode. By default this call will be ignored if the
 /// user has downloaded and registered the Authy smartphone application
-/// against their phone number. However you can override this behavior.
+/// against their phone number. However you can override this behavior with
+/// `force`.
 ///
 /// Custom Actions
 ///
@@ -220,17 +397,22 @@ fn phone(client: &Client, kind: &str, id: u32, force: bool, action: Option<&str>
 /// perform different actions on your app. When using this option you have to
 /// pass the same action when verifying the code.
 ///
+/// The message is sent in the language given by `locale`, and `custom_code`
+/// replaces the code Authy would have generated.
+///
 /// Please see the Authy documentation for more details:
 /// https://www.twilio.com/docs/api/authy/authy-totp#requesting-sms-codes
-pub fn sms(client: &Client, id: u32, force: bool, action: Option<&str>, action_message: Option<&str>) -> Result<(Status, PhoneCall), AuthyError> {
-    phone(client, "sms", id, force, action, action_message)
+pub fn sms(client: &Client, id: u32, options: &TokenRequestOptions) -> Result<(Status, PhoneCall), AuthyError> {
+    options.validate()?;
+
+    client.execute(&Token { kind: "sms", id, options })
 }
 
 /// Send token to user via phone call.
 ///
 /// For users that don't own a smartphone, and are having trouble with SMS 
 /// Tokens, Authy allows you to use phone calls instead. This call will be 
-/// ignored if the user is using the Authy Mobile app.
+/// ignored if the user is using the Authy Mobile app unless `force` is set.
 ///
 /// Custom Actions
 ///
@@ -239,10 +421,75 @@ pub fn sms(client: &Client, id: u32, force: bool, action: Option<&str>, action_m
 /// perform different actions on your app. When using this option you have to
 /// pass the same action when verifying the code.
 ///
+/// The call is made in the language given by `locale`, and `custom_code`
+/// replaces the code Authy would have generated.
+///
 /// Please see the Authy documentation for more details:
 /// https://www.twilio.com/docs/api/authy/authy-totp#phone-call-tokens
-pub fn call(client: &Client, id: u32, force: bool, action: Option<&str>, action_message: Option<&str>) -> Result<(Status, PhoneCall), AuthyError> {
-    phone(client, "call", id, force, action, action_message)
+pub fn call(client: &Client, id: u32, options: &TokenRequestOptions) -> Result<(Status, PhoneCall), AuthyError> {
+    options.validate()?;
+
+    client.execute(&Token { kind: "call", id, options })
+}
+
+struct GenerateQr<'a> {
+    id: u32,
+    label: Option<&'a str>,
+    size: Option<u16>,
+}
+
+impl<'a> Endpoint for GenerateQr<'a> {
+    type Response = QrCode;
+
+    fn method(&self) -> Method { Method::POST }
+    fn prefix(&self) -> &str { PREFIX }
+    fn path(&self) -> String { format!("users/{}/secret", self.id) }
+
+    fn form(&self) -> Vec<(String, String)> {
+        let mut form = Form::new();
+        form.optional("label", self.label)
+            .optional("qr_size", self.size);
+        form.into()
+    }
+}
+
+/// Generate a QR code for a TOTP authenticator app.
+///
+/// Lets the user add their account to any TOTP authenticator app, such as
+/// Google Authenticator, rather than the Authy app. `label` is the account
+/// name shown in the app and `size` is the width and height of the QR code in
+/// pixels, 300 when not given.
+///
+/// Please see the Authy documentation for more details:
+/// https://www.twilio.com/docs/authy/api/one-time-passwords#generate-a-qr-code
+pub fn generate_qr(client: &Client, id: u32, label: Option<&str>, size: Option<u16>) -> Result<(Status, QrCode), AuthyError> {
+    client.execute(&GenerateQr { id, label, size })
+}
+
+struct RegisterActivity<'a> {
+    id: u32,
+    data: Option<&'a HashMap<&'a str, String>>,
+    activity_type: ActivityType,
+    user_ip: &'a str,
+}
+
+impl<'a> Endpoint for RegisterActivity<'a> {
+    type Response = Empty;
+
+    fn method(&self) -> Method { Method::POST }
+    fn prefix(&self) -> &str { PREFIX }
+    fn path(&self) -> String { format!("users/{}/register_activity", self.id) }
+
+    fn form(&self) -> Vec<(String, String)> {
+        let mut form = Form::new();
+        form.field("type", &self.activity_type)
+            .field("user_ip", self.user_ip);
+
+        if let Some(data) = self.data {
+            form.nested("data", data);
+        }
+        form.into()
+    }
 }
 
 /// Register user activity with authy.
@@ -255,17 +502,7 @@ pub fn call(client: &Client, id: u32, force: bool, action: Option<&str>, action_
 /// Please see the Authy documentation for more details:
 /// https://www.twilio.com/docs/api/authy/authy-totp#register-user-activities
 pub fn register_activity(client: &Client, id: u32, data: Option<&HashMap<&str, String>>, activity_type: ActivityType, user_ip: &str) -> Result<Status, AuthyError> {
-    let mut params: Vec<(String, String)> = vec![];
-    params.push(("type".into(), activity_type.to_string()));
-    params.push(("user_ip".into(), user_ip.into()));
-
-    if let Some(data) = data {
-        for (k, v) in data {
-            params.push((format!("data[{}]", k), v.clone()));
-        }
-    }
-
-    let (status, _) = client.post(PREFIX, &format!("users/{}/register_activity", id), None, Some(params))?;
+    let (status, Empty) = client.execute(&RegisterActivity { id, data, activity_type, user_ip })?;
 
     Ok(status)
 }
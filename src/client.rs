@@ -3,9 +3,13 @@ use std::io::Read;
 use std::time::Duration;
 
 use reqwest::{self, StatusCode, Method, Url};
+use serde::Deserialize;
 use serde_json::{self, Value};
 
 use error::AuthyError;
+use response::{self, Response};
+use endpoint::Endpoint;
+use de;
 
 /// The Authy REST client.
 ///
@@ -19,6 +23,11 @@ pub struct Client {
     /// Duration of time to wait between retry attempts.
     pub retry_wait: u16,
 
+    /// When true, fields missing from a response are given their default
+    /// value instead of failing the request. Identifying fields such as ids
+    /// are still required.
+    pub lenient: bool,
+
     api_url: String,
     api_key: String,
     reqwest: reqwest::Client,
@@ -27,18 +36,32 @@ pub struct Client {
 /// Status message returned by every API request.
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Status {
+    #[serde(default, deserialize_with = "de::bool")]
     pub success: bool,
+    #[serde(default)]
     pub message: String,
 
     pub error_code: Option<String>,
 }
 
+impl Status {
+    /// Returns `AuthyError::Unsuccessful` when `success` is false.
+    pub fn check(self) -> Result<Status, AuthyError> {
+        if self.success {
+            Ok(self)
+        } else {
+            Err(AuthyError::Unsuccessful(self))
+        }
+    }
+}
+
 impl Client {
     /// Create a new client to the Authy service.
     pub fn new(api_url: &str, api_key: &str) -> Client {
         Client {
             retry_count: 3,
             retry_wait: 250,
+            lenient: false,
             api_url: api_url.into(), 
             api_key: api_key.into(),
             reqwest: reqwest::Client::new(),
@@ -48,57 +71,53 @@ impl Client {
     /// Send a `get` request to the Authy service. This is intended to be used
     /// by the library and not the user.
     pub fn get(&self, prefix: &str, path: &str, url_params: Option<Vec<(String, String)>>) -> Result<(Status, Value), AuthyError> {
-        self.request(Method::GET, self.url(prefix, path, url_params), None)
+        self.request(Method::GET, prefix, path, url_params.as_deref(), None, None)
     }
 
     /// Send a `post` request to the Authy service. This is intended to be used
     /// by the library and not the user.
     pub fn post(&self, prefix: &str, path: &str, url_params: Option<Vec<(String, String)>>, post_params: Option<Vec<(String, String)>>) -> Result<(Status, Value), AuthyError> {
-        self.request(Method::POST, self.url(prefix, path, url_params), post_params)
+        self.request(Method::POST, prefix, path, url_params.as_deref(), post_params.as_deref(), None)
     }
 
-    fn url(&self, prefix: &str, path: &str, params: Option<Vec<(String, String)>>) -> Url {
-        let base = format!("{api_url}/{prefix}/json/{path}", 
-                           api_url = self.api_url,
-                           prefix = prefix,
-                           path = path);
-        match params {
-            Some(params) => Url::parse_with_params(&base, params),
-            None => Url::parse(&base),
-        }.expect("Url to be valid")
+    /// Call an `Endpoint` of the Authy service.
+    pub fn execute<E: Endpoint>(&self, endpoint: &E) -> Result<(Status, E::Response), AuthyError> {
+        let query = endpoint.query();
+        let form = endpoint.form();
+
+        self.request(endpoint.method(),
+                     endpoint.prefix(),
+                     &endpoint.path(),
+                     if query.is_empty() { None } else { Some(&query) },
+                     if form.is_empty() { None } else { Some(&form) },
+                     endpoint.key())
     }
 
-    fn request(&self, method: Method, url: Url, params: Option<Vec<(String, String)>>) -> Result<(Status, Value), AuthyError> {
+    /// Send a request to the Authy service, decoding the payload found under
+    /// `key` (or the whole body when `key` is `None`) straight into `T`. This
+    /// is intended to be used by the library and not the user.
+    pub fn request<T: Response>(&self, method: Method, prefix: &str, path: &str, url_params: Option<&[(String, String)]>, post_params: Option<&[(String, String)]>, key: Option<&str>) -> Result<(Status, T), AuthyError> {
+        let url = self.url(prefix, path, url_params);
         let mut count = self.retry_count;
         loop {
-            let url = url.clone();
-            let mut res = match params.clone() {
-                Some(p) => self.reqwest.request(method.clone(), url).header("X-Authy-API-Key", self.api_key.clone()).form(&p).send()?,
-                None => self.reqwest.request(method.clone(), url).header("X-Authy-API-Key", self.api_key.clone()).send()?,
-            };
+            let mut req = self.reqwest.request(method.clone(), url.clone()).header("X-Authy-API-Key", self.api_key.as_str());
+            if let Some(params) = post_params {
+                req = req.form(params);
+            }
+            let mut res = req.send()?;
 
-            let mut body = String::new();
-            res.read_to_string(&mut body)?;
+            let mut body = Vec::new();
+            res.read_to_end(&mut body)?;
+
+            if res.status() == StatusCode::OK {
+                return self.decode(&body, key);
+            }
 
             // I wish could just check the content type but authy mixes json
             // and html content types when returning valid json.
-            match serde_json::from_str::<Value>(&body) {
-                Ok(mut value) => {
-                    // It seems that for whatever reason at least one call is returning
-                    // a *string* of a bool rather than a bool for success.
-                    value["success"] = match value.clone()["success"] {
-                        Value::Bool(v) => Value::Bool(v),
-                        Value::String(ref v) => match v.as_ref() {
-                            "true" => Value::Bool(true),
-                            _ => Value::Bool(false),
-                        },
-                        _ => Value::Bool(false),
-                    };
-
-                    let status: Status = serde_json::from_value(value.clone())?;
-
+            match serde_json::from_slice::<Status>(&body) {
+                Ok(status) => {
                     match res.status() {
-                        StatusCode::OK => return Ok((status, value)),
                         StatusCode::BAD_REQUEST => return Err(AuthyError::BadRequest(status)),
                         StatusCode::UNAUTHORIZED => return Err(AuthyError::UnauthorizedKey(status)),
                         StatusCode::FORBIDDEN => return Err(AuthyError::Forbidden(status)),
@@ -108,6 +127,7 @@ impl Client {
                         s => return Err(AuthyError::UnknownServerResponse(format!("Status code not covered in authy REST specification: {}", s))),
                     };
                 },
+                Err(ref e) if e.is_data() => return Err(AuthyError::JsonParseError(e.to_string())),
                 Err(_) => {
                     match res.status() {
                         StatusCode::SERVICE_UNAVAILABLE => {
@@ -126,4 +146,36 @@ impl Client {
             };
         }
     }
+
+    /// Decode a response body into its `Status` and the payload found under
+    /// `key`, honouring `lenient`. This is intended to be used by the library
+    /// and not the user.
+    pub fn decode<T: Response>(&self, body: &[u8], key: Option<&str>) -> Result<(Status, T), AuthyError> {
+        match response::decode(body, key) {
+            Ok(decoded) => Ok(decoded),
+            Err(ref e) if e.is_data() && self.lenient => {
+                let mut value: Value = serde_json::from_slice(body)?;
+                let status = Status::deserialize(&value)?;
+                let payload = match key {
+                    Some(key) => value[key].take(),
+                    None => value,
+                };
+
+                Ok((status, T::from_value_lenient(payload)?))
+            },
+            Err(ref e) if e.is_data() => Err(AuthyError::JsonParseError(e.to_string())),
+            Err(_) => Err(AuthyError::InvalidServerResponse),
+        }
+    }
+
+    fn url(&self, prefix: &str, path: &str, params: Option<&[(String, String)]>) -> Url {
+        let base = format!("{api_url}/{prefix}/json/{path}", 
+                           api_url = self.api_url,
+                           prefix = prefix,
+                           path = path);
+        match params {
+            Some(params) => Url::parse_with_params(&base, params),
+            None => Url::parse(&base),
+        }.expect("Url to be valid")
+    }
 }
@@ -0,0 +1,11 @@
+//! Comparing secrets, such as codes and hashes, without timing revealing
+//! how much of them matched.
+
+/// true when `a` and `b` are equal. Every byte is compared whatever the
+/// result, so only the lengths affect the time taken.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
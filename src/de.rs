@@ -0,0 +1,136 @@
+//! Lenient deserializers for the inconsistent types Authy returns.
+//!
+//! Depending on the endpoint, Authy will happily return numbers as strings,
+//! booleans as strings and `null` in place of an empty list. These are used
+//! with `#[serde(deserialize_with = "...")]` on the response types.
+use std::convert::TryFrom;
+use std::fmt;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+
+#[derive(Clone, Copy)]
+struct BoolVisitor;
+
+impl<'de> Visitor<'de> for BoolVisitor {
+    type Value = bool;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a boolean, or a string or number representing one")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<bool, E> {
+        Ok(v)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<bool, E> {
+        match v {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(E::invalid_value(de::Unexpected::Unsigned(v), &self)),
+        }
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<bool, E> {
+        match v {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(E::invalid_value(de::Unexpected::Signed(v), &self)),
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<bool, E> {
+        match v.trim() {
+            s if s.eq_ignore_ascii_case("true") || s == "1" => Ok(true),
+            s if s.eq_ignore_ascii_case("false") || s == "0" => Ok(false),
+            _ => Err(E::invalid_value(de::Unexpected::Str(v), &self)),
+        }
+    }
+}
+
+/// Accepts `true`, `"true"`, `1` and `"1"` (and their false counterparts).
+pub fn bool<'de, D: Deserializer<'de>>(d: D) -> Result<bool, D::Error> {
+    d.deserialize_any(BoolVisitor)
+}
+
+struct OptionVisitor<V>(V);
+
+impl<'de, V: Visitor<'de> + Copy> Visitor<'de> for OptionVisitor<V> {
+    type Value = Option<V::Value>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.expecting(f)?;
+        write!(f, " or null")
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, d: D) -> Result<Self::Value, D::Error> {
+        d.deserialize_any(self.0).map(Some)
+    }
+}
+
+/// Like `bool`, but also accepts `null`.
+pub fn option_bool<'de, D: Deserializer<'de>>(d: D) -> Result<Option<bool>, D::Error> {
+    d.deserialize_option(OptionVisitor(BoolVisitor))
+}
+
+#[derive(Clone, Copy)]
+struct UnsignedVisitor<T>(PhantomData<T>);
+
+impl<'de, T: TryFrom<u64> + FromStr> Visitor<'de> for UnsignedVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an unsigned integer, or a string containing one")
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<T, E> {
+        T::try_from(v).map_err(|_| E::invalid_value(de::Unexpected::Unsigned(v), &self))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<T, E> {
+        u64::try_from(v).ok()
+            .and_then(|v| T::try_from(v).ok())
+            .ok_or_else(|| E::invalid_value(de::Unexpected::Signed(v), &self))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<T, E> {
+        v.trim().parse().map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+    }
+}
+
+/// Accepts `1234` and `"1234"`.
+pub fn u16<'de, D: Deserializer<'de>>(d: D) -> Result<u16, D::Error> {
+    d.deserialize_any(UnsignedVisitor(PhantomData))
+}
+
+/// Accepts `1234` and `"1234"`.
+pub fn u32<'de, D: Deserializer<'de>>(d: D) -> Result<u32, D::Error> {
+    d.deserialize_any(UnsignedVisitor(PhantomData))
+}
+
+/// Like `u32`, but also accepts `null`.
+pub fn option_u32<'de, D: Deserializer<'de>>(d: D) -> Result<Option<u32>, D::Error> {
+    d.deserialize_option(OptionVisitor(UnsignedVisitor(PhantomData)))
+}
+
+/// Like `u32`, but for `u64`s that may also be `null`.
+pub fn option_u64<'de, D: Deserializer<'de>>(d: D) -> Result<Option<u64>, D::Error> {
+    d.deserialize_option(OptionVisitor(UnsignedVisitor(PhantomData)))
+}
+
+/// Accepts a list, treating `null` as an empty list.
+pub fn vec<'de, D, T>(d: D) -> Result<Vec<T>, D::Error>
+    where D: Deserializer<'de>,
+          T: Deserialize<'de>,
+{
+    Ok(Option::deserialize(d)?.unwrap_or_default())
+}
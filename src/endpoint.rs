@@ -0,0 +1,55 @@
+pub use reqwest::Method;
+
+use response::Response;
+
+/// Describes a single endpoint of the Authy API.
+///
+/// Every function in [api](api/index.html) is built on top of an `Endpoint`.
+/// Implement it for your own types to call endpoints this crate doesn't cover
+/// yet, and send them with `Client::execute`.
+///
+/// Example:
+///
+/// ```rust,ignore
+/// struct AppDetails;
+///
+/// impl Endpoint for AppDetails {
+///     type Response = Details;
+///
+///     fn method(&self) -> Method { Method::GET }
+///     fn prefix(&self) -> &str { "protected" }
+///     fn path(&self) -> String { "app/details".into() }
+///     fn key(&self) -> Option<&str> { Some("app") }
+/// }
+///
+/// let (status, details) = c.execute(&AppDetails)?;
+/// ```
+pub trait Endpoint {
+    /// The payload returned by the endpoint.
+    type Response: Response;
+
+    /// The HTTP method used to call the endpoint.
+    fn method(&self) -> Method;
+
+    /// The API the endpoint belongs to, such as `protected` or `onetouch`.
+    fn prefix(&self) -> &str;
+
+    /// The path of the endpoint following `{prefix}/json/`.
+    fn path(&self) -> String;
+
+    /// Parameters sent in the query string.
+    fn query(&self) -> Vec<(String, String)> {
+        vec![]
+    }
+
+    /// Parameters sent as a url encoded form body.
+    fn form(&self) -> Vec<(String, String)> {
+        vec![]
+    }
+
+    /// The field of the response holding the payload, or `None` when the
+    /// payload is the response itself.
+    fn key(&self) -> Option<&str> {
+        None
+    }
+}
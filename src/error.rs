@@ -1,11 +1,13 @@
 use std::error;
 use std::io;
+use std::time::Duration;
 use std::fmt;
 
 use reqwest;
 use serde_json;
 
 use client::Status;
+use guard::Violation;
 
 /// The error type used by this library.
 #[derive(PartialEq, Eq, Clone, Debug)]
@@ -31,6 +33,9 @@ pub enum AuthyError {
     /// The authy service was unavailable. Only returned after the configured `retry_count`.
     ServiceUnavailable, // 503
 
+    /// The request was answered, but Authy reported it wasn't successful.
+    Unsuccessful(Status),
+
     /// There was an IO error.
     IoError(String),
 
@@ -45,6 +50,44 @@ pub enum AuthyError {
 
     /// Server responded with something we don't know how to make use of.
     UnknownServerResponse(String),
+
+    /// The request was rejected before being sent to the server.
+    InvalidRequest(String),
+
+    /// A callback from Authy failed signature or nonce verification.
+    InvalidSignature(String),
+
+    /// The details of an approved request don't match those that were sent.
+    TransactionMismatch(String),
+
+    /// A code was sent too recently. Another can be sent after the duration.
+    ResendCooldown(Duration),
+
+    /// The limit on codes sent or checked has been reached.
+    AttemptsExceeded(String),
+
+    /// A configuration file couldn't be parsed.
+    ConfigError(String),
+
+    /// A `Guard` refused to send a code.
+    GuardViolation(Violation),
+}
+
+impl AuthyError {
+    /// The `Status` returned by Authy, for errors that carry one.
+    pub fn status(&self) -> Option<&Status> {
+        use AuthyError::*;
+        match *self {
+            BadRequest(ref s) | UnauthorizedKey(ref s) | Forbidden(ref s) | UserNotFound(ref s)
+                | TooManyRequests(ref s) | InternalServerError(ref s) | Unsuccessful(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// The Authy error code, for errors that carry one.
+    pub fn error_code(&self) -> Option<&str> {
+        self.status().and_then(|s| s.error_code.as_deref())
+    }
 }
 
 impl error::Error for AuthyError {
@@ -58,11 +101,19 @@ impl error::Error for AuthyError {
             TooManyRequests(_) => "429 too many requests",
             InternalServerError(_) => "500 internal server error",
             ServiceUnavailable => "503 service unavailable",
+            Unsuccessful(_) => "Unsuccessful",
             IoError(_) => "IO error",
             JsonParseError(_) => "JSON parse error",
             RequestError(_) => "Request error",
             InvalidServerResponse => "Invalid server response",
-            UnknownServerResponse(_) => "Unknown server response"
+            UnknownServerResponse(_) => "Unknown server response",
+            InvalidRequest(_) => "Invalid request",
+            InvalidSignature(_) => "Invalid signature",
+            TransactionMismatch(_) => "Transaction mismatch",
+            ResendCooldown(_) => "Resend cooldown",
+            AttemptsExceeded(_) => "Attempts exceeded",
+            ConfigError(_) => "Configuration error",
+            GuardViolation(_) => "Guard violation",
         }
     }
     fn cause(&self) -> Option<&error::Error> {
@@ -82,11 +133,19 @@ impl fmt::Display for AuthyError {
             TooManyRequests(ref s) => write!(f, "Too Many Requests: {}", s.message),
             InternalServerError(ref s) => write!(f, "Internal Server Error: {}", s.message),
             ServiceUnavailable => write!(f, "Service Unavailable reported by authy service"),
+            Unsuccessful(ref s) => write!(f, "Unsuccessful: {}", s.message),
             IoError(ref s) => write!(f, "IO Error: {}", s),
             JsonParseError(ref s) => write!(f, "Json parsing error: {}", s),
             RequestError(ref s) => write!(f, "Request error: {}", s),
             InvalidServerResponse => write!(f, "Server returned an invalid response"),
             UnknownServerResponse(ref s) => write!(f, "Server returned a response we don't know how to process: {}", s),
+            InvalidRequest(ref s) => write!(f, "Invalid request: {}", s),
+            InvalidSignature(ref s) => write!(f, "Invalid signature: {}", s),
+            TransactionMismatch(ref s) => write!(f, "Transaction does not match: {}", s),
+            ResendCooldown(ref d) => write!(f, "Resend cooldown: try again in {} seconds", d.as_secs() + u64::from(d.subsec_nanos() > 0)),
+            AttemptsExceeded(ref s) => write!(f, "Attempts exceeded: {}", s),
+            ConfigError(ref s) => write!(f, "Configuration error: {}", s),
+            GuardViolation(ref v) => write!(f, "Guard violation: {}", v),
         }
     }
 }
@@ -0,0 +1,169 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{BuildHasher, Hash};
+
+use serde_json::Value;
+
+/// A parameter value, which may be nested.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Param {
+    /// A single value, sent as `key=value`.
+    Value(String),
+
+    /// Named values, sent as `key[name]=value`.
+    Map(Vec<(String, Param)>),
+
+    /// A list of values, sent as `key[]=value`. A list of maps is sent as
+    /// `key[][name]=value`, with one group of names per item.
+    List(Vec<Param>),
+}
+
+impl Param {
+    /// Build a `Param::Map` from any iterator of names and values.
+    pub fn map<I, K, V>(entries: I) -> Param
+        where I: IntoIterator<Item = (K, V)>,
+              K: ToString,
+              V: Into<Param>,
+    {
+        Param::Map(entries.into_iter().map(|(k, v)| (k.to_string(), v.into())).collect())
+    }
+
+    /// Build a `Param::List` from any iterator of values.
+    pub fn list<I, V>(items: I) -> Param
+        where I: IntoIterator<Item = V>,
+              V: Into<Param>,
+    {
+        Param::List(items.into_iter().map(Into::into).collect())
+    }
+
+    fn encode(&self, key: String, params: &mut Vec<(String, String)>) {
+        match *self {
+            Param::Value(ref v) => params.push((key, v.clone())),
+            Param::Map(ref entries) => {
+                for (k, v) in entries {
+                    v.encode(format!("{}[{}]", key, k), params);
+                }
+            },
+            Param::List(ref items) => {
+                for v in items {
+                    v.encode(format!("{}[]", key), params);
+                }
+            },
+        }
+    }
+}
+
+impl From<String> for Param {
+    fn from(v: String) -> Param {
+        Param::Value(v)
+    }
+}
+
+impl<'a> From<&'a str> for Param {
+    fn from(v: &'a str) -> Param {
+        Param::Value(v.into())
+    }
+}
+
+impl<'a> From<&'a String> for Param {
+    fn from(v: &'a String) -> Param {
+        Param::Value(v.clone())
+    }
+}
+
+impl<'a, K: ToString, V: Into<Param> + Clone> From<&'a BTreeMap<K, V>> for Param {
+    fn from(v: &'a BTreeMap<K, V>) -> Param {
+        Param::map(v.iter().map(|(k, v)| (k.to_string(), v.clone())))
+    }
+}
+
+impl<'a, K: ToString + Eq + Hash, V: Into<Param> + Clone, S: BuildHasher> From<&'a HashMap<K, V, S>> for Param {
+    fn from(v: &'a HashMap<K, V, S>) -> Param {
+        Param::map(v.iter().map(|(k, v)| (k.to_string(), v.clone())))
+    }
+}
+
+impl<'a> From<&'a Value> for Param {
+    /// Objects become maps and arrays become lists. `null` is sent as an
+    /// empty value.
+    fn from(v: &'a Value) -> Param {
+        match *v {
+            Value::Null => Param::Value(String::new()),
+            Value::String(ref s) => Param::Value(s.clone()),
+            Value::Object(ref o) => Param::map(o.iter()),
+            Value::Array(ref a) => Param::list(a.iter()),
+            ref other => Param::Value(other.to_string()),
+        }
+    }
+}
+
+/// Builds the url encoded parameters sent to Authy, including Rails style
+/// nested parameters such as `details[Amount]` and `logos[][res]`.
+///
+/// Example:
+///
+/// ```rust
+/// use authy::{Form, Param};
+///
+/// let mut form = Form::new();
+/// form.field("message", "Login requested")
+///     .nested("logos", Param::list(vec![
+///         Param::map(vec![("res", "default"), ("url", "https://example.com/logo.png")]),
+///     ]));
+///
+/// let params: Vec<(String, String)> = form.into();
+/// assert_eq!(params, vec![
+///     ("message".to_string(), "Login requested".to_string()),
+///     ("logos[][res]".to_string(), "default".to_string()),
+///     ("logos[][url]".to_string(), "https://example.com/logo.png".to_string()),
+/// ]);
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Form {
+    params: Vec<(String, String)>,
+}
+
+impl Form {
+    pub fn new() -> Form {
+        Form::default()
+    }
+
+    /// Add `key=value`.
+    pub fn field<V: ToString>(&mut self, key: &str, value: V) -> &mut Form {
+        self.params.push((key.into(), value.to_string()));
+        self
+    }
+
+    /// Add `key=value` when there is a value.
+    pub fn optional<V: ToString>(&mut self, key: &str, value: Option<V>) -> &mut Form {
+        if let Some(value) = value {
+            self.field(key, value);
+        }
+        self
+    }
+
+    /// Add `key[name]=value` for each of the entries.
+    pub fn map<I, K, V>(&mut self, key: &str, entries: I) -> &mut Form
+        where I: IntoIterator<Item = (K, V)>,
+              K: ToString,
+              V: Into<Param>,
+    {
+        self.nested(key, Param::map(entries))
+    }
+
+    /// Add a parameter of any shape under `key`.
+    pub fn nested<P: Into<Param>>(&mut self, key: &str, param: P) -> &mut Form {
+        param.into().encode(key.into(), &mut self.params);
+        self
+    }
+
+    /// The parameters added so far, in order.
+    pub fn params(&self) -> &[(String, String)] {
+        &self.params
+    }
+}
+
+impl From<Form> for Vec<(String, String)> {
+    fn from(form: Form) -> Vec<(String, String)> {
+        form.params
+    }
+}
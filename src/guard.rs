@@ -0,0 +1,324 @@
+//! Protection against SMS pumping and toll fraud.
+//!
+//! Attackers trigger codes to premium-rate numbers they are paid for. A
+//! `Guard` sits in front of `api::user::sms`, `api::user::call` and
+//! `api::phone::start`, and refuses to send once a number, country, IP or
+//! user has asked for too many codes:
+//!
+//! ```rust,ignore
+//! let config = GuardConfig::from_file("guard.toml")?;
+//! let guard = Guard::new(config, Arc::new(FileStore::new("guard.json")));
+//!
+//! match guard.start(&c, ContactType::SMS, 44, "7700 900123", Some(ip), &PhoneStartOptions::default()) {
+//!     Err(AuthyError::GuardViolation(v)) => println!("Refused: {}", v),
+//!     other => (),
+//! }
+//! ```
+//!
+//! Limits are counted over fixed windows, which start with the first
+//! attempt counted. They are checked from the narrowest to the broadest: the
+//! IP address, then the user, then the number's prefixes, its country and
+//! finally the global limit. A refused attempt still counts against the
+//! limits checked up to and including the one that refused it, so one
+//! IP address or user being refused doesn't use up the limits shared with
+//! everyone else.
+
+use std::fmt::{self, Display};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use toml;
+
+use error::AuthyError;
+use client::{Client, Status};
+use store::Store;
+use api;
+use api::user::{PhoneCall, TokenRequestOptions};
+use api::phone::{ContactType, PhoneStart, PhoneStartOptions};
+
+const PREFIX: &str = "authy:guard";
+
+/// At most `max` attempts every `window_secs` seconds.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Limit {
+    pub max: u32,
+    pub window_secs: u64,
+}
+
+impl Limit {
+    pub fn new(max: u32, window: Duration) -> Limit {
+        Limit { max, window_secs: window.as_secs() }
+    }
+
+    pub fn window(&self) -> Duration {
+        Duration::from_secs(self.window_secs)
+    }
+}
+
+/// A limit for a single country.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CountryLimit {
+    pub country_code: u16,
+    pub max: u32,
+    pub window_secs: u64,
+}
+
+/// A limit for the numbers starting with `prefix`, which includes the
+/// country code, such as `4470`. Characters other than digits are ignored.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PrefixLimit {
+    pub prefix: String,
+    pub max: u32,
+    pub window_secs: u64,
+}
+
+/// The limits enforced by a `Guard`.
+///
+/// Each limit is off unless it is set. A config can be built in code or
+/// loaded from TOML, where unknown keys are rejected:
+///
+/// ```toml
+/// allowed_country_codes = [1, 44]
+/// per_country = { max = 1000, window_secs = 3600 }
+/// per_ip = { max = 10, window_secs = 3600 }
+/// per_user = { max = 5, window_secs = 3600 }
+/// numbers_per_ip = { max = 3, window_secs = 86400 }
+/// global = { max = 5000, window_secs = 3600 }
+///
+/// [[country_limits]]
+/// country_code = 44
+/// max = 200
+/// window_secs = 3600
+///
+/// [[prefix_limits]]
+/// prefix = "4470"
+/// max = 10
+/// window_secs = 86400
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GuardConfig {
+    /// The only country codes that codes are sent to. Empty allows every
+    /// country.
+    pub allowed_country_codes: Vec<u16>,
+
+    /// The limit for each country without an entry in `country_limits`.
+    pub per_country: Option<Limit>,
+
+    /// Limits for single countries, replacing `per_country`.
+    pub country_limits: Vec<CountryLimit>,
+
+    /// Limits for ranges of numbers. Every prefix a number starts with is
+    /// counted.
+    pub prefix_limits: Vec<PrefixLimit>,
+
+    /// The limit for each IP address.
+    pub per_ip: Option<Limit>,
+
+    /// The limit for each Authy ID.
+    pub per_user: Option<Limit>,
+
+    /// The most different numbers a single IP address can send codes to.
+    pub numbers_per_ip: Option<Limit>,
+
+    /// The limit across every destination, to catch attacks spread thinly
+    /// over many numbers and countries.
+    pub global: Option<Limit>,
+}
+
+impl GuardConfig {
+    /// Parse a config from TOML.
+    pub fn from_toml(s: &str) -> Result<GuardConfig, AuthyError> {
+        toml::from_str(s).map_err(|e| AuthyError::ConfigError(e.to_string()))
+    }
+
+    /// Load a config from a TOML file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<GuardConfig, AuthyError> {
+        GuardConfig::from_toml(&fs::read_to_string(path)?)
+    }
+}
+
+/// Why a `Guard` refused to send a code.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Violation {
+    /// The country code isn't in `allowed_country_codes`.
+    CountryNotAllowed(u16),
+
+    /// Too many codes were sent to the country.
+    CountryLimit(u16),
+
+    /// Too many codes were sent to numbers starting with the prefix.
+    PrefixLimit(String),
+
+    /// Too many codes were asked for from the IP address.
+    IpLimit(String),
+
+    /// Too many codes were sent to the Authy ID.
+    UserLimit(u32),
+
+    /// Codes were asked for to too many numbers from the IP address.
+    NumbersPerIp(String),
+
+    /// Too many codes were sent overall.
+    GlobalLimit,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Violation::CountryNotAllowed(c) => write!(f, "country code {} is not allowed", c),
+            Violation::CountryLimit(c) => write!(f, "too many codes sent to country code {}", c),
+            Violation::PrefixLimit(ref p) => write!(f, "too many codes sent to numbers starting with {}", p),
+            Violation::IpLimit(ref ip) => write!(f, "too many codes requested from {}", ip),
+            Violation::UserLimit(id) => write!(f, "too many codes sent to user {}", id),
+            Violation::NumbersPerIp(ref ip) => write!(f, "codes requested for too many numbers from {}", ip),
+            Violation::GlobalLimit => write!(f, "too many codes sent"),
+        }
+    }
+}
+
+/// A code about to be sent.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Attempt<'a> {
+    pub country_code: u16,
+
+    /// The number without the country code. Formatting is ignored.
+    pub phone: &'a str,
+
+    /// The Authy ID, when sending to a user.
+    pub authy_id: Option<u32>,
+
+    /// The IP address the code was asked for from.
+    pub user_ip: Option<&'a str>,
+}
+
+/// Enforces a `GuardConfig`, keeping its counters in a `Store`.
+///
+/// Share the store between the app servers to enforce the limits across all
+/// of them.
+#[derive(Clone)]
+pub struct Guard {
+    pub config: GuardConfig,
+    store: Arc<dyn Store>,
+}
+
+impl Guard {
+    pub fn new(config: GuardConfig, store: Arc<dyn Store>) -> Guard {
+        Guard { config, store }
+    }
+
+    /// Count `attempt` against the limits.
+    ///
+    /// Returns `AuthyError::GuardViolation` with the first limit it exceeds.
+    pub fn check(&self, attempt: &Attempt) -> Result<(), AuthyError> {
+        let config = &self.config;
+        let cc = attempt.country_code;
+        let number: String = format!("{}{}", cc, attempt.phone).chars()
+            .filter(|c| c.is_ascii_digit())
+            .collect();
+
+        if !config.allowed_country_codes.is_empty() && !config.allowed_country_codes.contains(&cc) {
+            return Err(violation(Violation::CountryNotAllowed(cc)));
+        }
+
+        if let Some(ip) = attempt.user_ip {
+            if let Some(limit) = config.per_ip {
+                self.count(&format!("ip:{}", ip), limit, Violation::IpLimit(ip.into()))?;
+            }
+
+            // A number is only marked as seen once it is allowed, so a
+            // refused number is refused again when retried.
+            if let Some(limit) = config.numbers_per_ip {
+                let seen = format!("{}:ip_number:{}:{}", PREFIX, ip, number);
+                if self.store.add(&seen, "1", Some(limit.window()))? {
+                    if let Err(e) = self.count(&format!("numbers_per_ip:{}", ip), limit, Violation::NumbersPerIp(ip.into())) {
+                        self.store.remove(&seen)?;
+                        return Err(e);
+                    }
+                }
+            }
+        }
+
+        if let (Some(id), Some(limit)) = (attempt.authy_id, config.per_user) {
+            self.count(&format!("user:{}", id), limit, Violation::UserLimit(id))?;
+        }
+
+        for l in &config.prefix_limits {
+            let prefix: String = l.prefix.chars().filter(|c| c.is_ascii_digit()).collect();
+            if !prefix.is_empty() && number.starts_with(&prefix) {
+                let limit = Limit { max: l.max, window_secs: l.window_secs };
+                self.count(&format!("prefix:{}", prefix), limit, Violation::PrefixLimit(prefix.clone()))?;
+            }
+        }
+
+        let country = config.country_limits.iter()
+            .find(|l| l.country_code == cc)
+            .map(|l| Limit { max: l.max, window_secs: l.window_secs })
+            .or(config.per_country);
+        if let Some(limit) = country {
+            self.count(&format!("country:{}", cc), limit, Violation::CountryLimit(cc))?;
+        }
+
+        if let Some(limit) = config.global {
+            self.count("global", limit, Violation::GlobalLimit)?;
+        }
+
+        Ok(())
+    }
+
+    fn count(&self, key: &str, limit: Limit, v: Violation) -> Result<(), AuthyError> {
+        let count = self.store.increment(&format!("{}:{}", PREFIX, key), Some(limit.window()))?;
+        if count > u64::from(limit.max) {
+            return Err(violation(v));
+        }
+        Ok(())
+    }
+
+    /// Send a token to user `id`, whose number is `phone`, via SMS.
+    ///
+    /// `country_code` and `phone` must be the number the user registered
+    /// with Authy, such as from your own records, since that is where the
+    /// token goes. Authy doesn't return it unmasked, so it can't be looked up
+    /// here, and counting any other number lets the limits be bypassed.
+    ///
+    /// Please see [api::user::sms](../api/user/fn.sms.html)
+    pub fn sms(&self, client: &Client, id: u32, country_code: u16, phone: &str, user_ip: Option<&str>, options: &TokenRequestOptions) -> Result<(Status, PhoneCall), AuthyError> {
+        options.validate()?;
+        self.check(&Attempt { country_code, phone, authy_id: Some(id), user_ip })?;
+
+        api::user::sms(client, id, options)
+    }
+
+    /// Send a token to user `id`, whose number is `phone`, via phone call.
+    ///
+    /// As with `sms`, `country_code` and `phone` must be the number the user
+    /// registered with Authy.
+    ///
+    /// Please see [api::user::call](../api/user/fn.call.html)
+    pub fn call(&self, client: &Client, id: u32, country_code: u16, phone: &str, user_ip: Option<&str>, options: &TokenRequestOptions) -> Result<(Status, PhoneCall), AuthyError> {
+        options.validate()?;
+        self.check(&Attempt { country_code, phone, authy_id: Some(id), user_ip })?;
+
+        api::user::call(client, id, options)
+    }
+
+    /// Start a phone verification.
+    ///
+    /// Please see [api::phone::start](../api/phone/fn.start.html)
+    pub fn start(&self, client: &Client, via: ContactType, country_code: u16, phone: &str, user_ip: Option<&str>, options: &PhoneStartOptions) -> Result<(Status, PhoneStart), AuthyError> {
+        options.validate()?;
+        self.check(&Attempt { country_code, phone, authy_id: None, user_ip })?;
+
+        api::phone::start(client, via, country_code, phone, options)
+    }
+}
+
+fn violation(v: Violation) -> AuthyError {
+    AuthyError::GuardViolation(v)
+}
@@ -41,7 +41,7 @@
 //!     println!("We have a user: {:#?}", user);
 //!
 //!     let code = "000000"; // Pretend user has provided a valid code
-//!     match user::verify(&c, user.id, code) {
+//!     match user::verify(&c, user.id, code, &user::VerifyOptions::default()) {
 //!         Ok(_) => println!("Congrats on being validated!"),
 //!         Err(AuthyError::UnauthorizedKey(e)) => println!("Token provided by the user was wrong"),
 //!         Err(e) => println!("Some server error: {:?}", e),
@@ -50,7 +50,13 @@
 //!     // Lets send out a sms token just for fun
 //!     // Must be using a real API key on the production authy server for this to
 //!     // actually send out anything.
-//!     user::sms(&c, user.id, true, Some("login"), Some("Authy documentation example login")).unwrap();
+//!     let options = user::TokenRequestOptions {
+//!         force: true,
+//!         action: Some("login"),
+//!         action_message: Some("Authy documentation example login"),
+//!         ..user::TokenRequestOptions::default()
+//!     };
+//!     user::sms(&c, user.id, &options).unwrap();
 //! }
 //! ```
 //!
@@ -59,7 +65,7 @@
 //!
 //! ```rust
 //! extern crate authy;
-//! use authy::{Client, User};
+//! use authy::{Client, User, VerifyOptions, VerifyOutcome, TokenRequestOptions};
 //!
 //! fn main() {
 //!     let api_url = "https://sandbox-api.authy.com";
@@ -81,19 +87,33 @@
 //!     println!("We have a user: {:#?}", user);
 //!
 //!     let code = "000000"; // Pretend user has provided a valid code
-//!     if user.verify(&c, code).unwrap() {
+//!     if user.verify(&c, code, &VerifyOptions::default()).unwrap() == VerifyOutcome::Valid {
 //!         println!("Congrats on being validated!");
 //!     }
 //!
 //!     // Lets send out a sms token just for fun
 //!     // Must be using a real API key on the production authy server for this to
 //!     // actually send out anything.
-//!     user.sms(&c, true, Some("login"), Some("Authy documentation example login")).unwrap();
+//!     let options = TokenRequestOptions {
+//!         force: true,
+//!         action: Some("login"),
+//!         action_message: Some("Authy documentation example login"),
+//!         ..TokenRequestOptions::default()
+//!     };
+//!     user.sms(&c, &options).unwrap();
 //! }
 //! ```
 
 extern crate reqwest;
+extern crate hmac;
+extern crate sha1;
+extern crate sha2;
+extern crate base64;
+extern crate toml;
+extern crate getrandom;
+extern crate pbkdf2;
 
+extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate serde_json;
@@ -104,11 +124,51 @@ pub use error::AuthyError;
 mod client;
 pub use client::{Client, Status};
 
+mod response;
+pub use response::{Response, Empty};
+
+mod de;
+
+mod compare;
+
+mod endpoint;
+pub use endpoint::{Endpoint, Method};
+
+mod form;
+pub use form::{Form, Param};
+
+mod locale;
+pub use locale::Locale;
+
 pub mod api;
 
 pub mod user;
 
-pub use user::{User, PhoneCall, ActivityType};
+pub use user::{User, PhoneCall, ActivityType, VerifyOptions, VerifyOutcome, TokenRequestOptions, SendCooldown, CooldownMode, SendOutcome};
 
 pub mod phone;
-pub use phone::{Phone, ContactType, PhoneStart};
+pub use phone::{Phone, ContactType, PhoneStart, PhoneStartOptions, PhoneCheckOutcome};
+
+pub mod session;
+pub use session::{VerificationSession, SessionConfig};
+
+pub mod policy;
+pub use policy::{PhonePolicy, Decision};
+
+pub mod totp;
+pub use totp::{Totp, Algorithm};
+
+pub mod recovery;
+pub use recovery::RecoveryCodes;
+
+pub mod replay;
+pub use replay::ReplayCache;
+
+pub mod store;
+pub use store::{Store, MemoryStore, FileStore};
+
+pub mod guard;
+pub use guard::{Guard, GuardConfig};
+
+pub mod onetouch;
+pub use onetouch::{OneTouch, ApprovalOutcome};
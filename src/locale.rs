@@ -0,0 +1,244 @@
+//! The languages Authy can send verification messages in.
+
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use error::AuthyError;
+
+/// A language supported by Authy for SMS and voice messages.
+///
+/// Parse one from a BCP-47 tag such as `pt-BR`. Tags Authy doesn't support
+/// exactly fall back to the closest supported language, so `de-AT` becomes
+/// `German` and `zh-Hant-TW` becomes `ChineseTraditional`:
+///
+/// ```rust
+/// use authy::Locale;
+///
+/// assert_eq!("pt_BR".parse(), Ok(Locale::PortugueseBR));
+/// assert_eq!("de-AT".parse(), Ok(Locale::German));
+/// assert!("xx".parse::<Locale>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Locale {
+    Afrikaans,
+    Arabic,
+    Catalan,
+    Chinese,
+    ChineseSimplified,
+    ChineseTraditional,
+    Croatian,
+    Czech,
+    Danish,
+    Dutch,
+    English,
+    EnglishGB,
+    Finnish,
+    French,
+    German,
+    Greek,
+    Hebrew,
+    Hindi,
+    Hungarian,
+    Indonesian,
+    Italian,
+    Japanese,
+    Korean,
+    Malay,
+    Norwegian,
+    Polish,
+    Portuguese,
+    PortugueseBR,
+    Romanian,
+    Russian,
+    Spanish,
+    Swedish,
+    Tagalog,
+    Thai,
+    Turkish,
+    Vietnamese,
+}
+
+const LOCALES: &[(&str, Locale)] = &[
+    ("af", Locale::Afrikaans),
+    ("ar", Locale::Arabic),
+    ("ca", Locale::Catalan),
+    ("zh", Locale::Chinese),
+    ("zh-CN", Locale::ChineseSimplified),
+    ("zh-HK", Locale::ChineseTraditional),
+    ("hr", Locale::Croatian),
+    ("cs", Locale::Czech),
+    ("da", Locale::Danish),
+    ("nl", Locale::Dutch),
+    ("en", Locale::English),
+    ("en-GB", Locale::EnglishGB),
+    ("fi", Locale::Finnish),
+    ("fr", Locale::French),
+    ("de", Locale::German),
+    ("el", Locale::Greek),
+    ("he", Locale::Hebrew),
+    ("hi", Locale::Hindi),
+    ("hu", Locale::Hungarian),
+    ("id", Locale::Indonesian),
+    ("it", Locale::Italian),
+    ("ja", Locale::Japanese),
+    ("ko", Locale::Korean),
+    ("ms", Locale::Malay),
+    ("nb", Locale::Norwegian),
+    ("pl", Locale::Polish),
+    ("pt", Locale::Portuguese),
+    ("pt-BR", Locale::PortugueseBR),
+    ("ro", Locale::Romanian),
+    ("ru", Locale::Russian),
+    ("es", Locale::Spanish),
+    ("sv", Locale::Swedish),
+    ("tl", Locale::Tagalog),
+    ("th", Locale::Thai),
+    ("tr", Locale::Turkish),
+    ("vi", Locale::Vietnamese),
+];
+
+// Tags that name a supported language differently, including deprecated
+// language codes still sent by some platforms.
+const ALIASES: &[(&str, Locale)] = &[
+    ("zh-Hans", Locale::ChineseSimplified),
+    ("zh-SG", Locale::ChineseSimplified),
+    ("zh-Hant", Locale::ChineseTraditional),
+    ("zh-TW", Locale::ChineseTraditional),
+    ("zh-MO", Locale::ChineseTraditional),
+    ("en-UK", Locale::EnglishGB),
+    ("no", Locale::Norwegian),
+    ("nn", Locale::Norwegian),
+    ("iw", Locale::Hebrew),
+    ("in", Locale::Indonesian),
+    ("fil", Locale::Tagalog),
+];
+
+// The language spoken by most people for a calling code, where there is one.
+const COUNTRY_CODES: &[(u16, Locale)] = &[
+    (1, Locale::English),
+    (7, Locale::Russian),
+    (20, Locale::Arabic),
+    (27, Locale::English),
+    (30, Locale::Greek),
+    (31, Locale::Dutch),
+    (33, Locale::French),
+    (34, Locale::Spanish),
+    (36, Locale::Hungarian),
+    (39, Locale::Italian),
+    (40, Locale::Romanian),
+    (43, Locale::German),
+    (44, Locale::EnglishGB),
+    (45, Locale::Danish),
+    (46, Locale::Swedish),
+    (47, Locale::Norwegian),
+    (48, Locale::Polish),
+    (49, Locale::German),
+    (51, Locale::Spanish),
+    (52, Locale::Spanish),
+    (54, Locale::Spanish),
+    (55, Locale::PortugueseBR),
+    (56, Locale::Spanish),
+    (57, Locale::Spanish),
+    (58, Locale::Spanish),
+    (60, Locale::Malay),
+    (61, Locale::English),
+    (62, Locale::Indonesian),
+    (63, Locale::Tagalog),
+    (64, Locale::English),
+    (66, Locale::Thai),
+    (81, Locale::Japanese),
+    (82, Locale::Korean),
+    (84, Locale::Vietnamese),
+    (86, Locale::ChineseSimplified),
+    (90, Locale::Turkish),
+    (351, Locale::Portuguese),
+    (353, Locale::English),
+    (358, Locale::Finnish),
+    (385, Locale::Croatian),
+    (420, Locale::Czech),
+    (852, Locale::ChineseTraditional),
+    (853, Locale::ChineseTraditional),
+    (886, Locale::ChineseTraditional),
+    (966, Locale::Arabic),
+    (971, Locale::Arabic),
+    (972, Locale::Hebrew),
+];
+
+impl Locale {
+    /// The tag sent to Authy, such as `pt-BR`.
+    pub fn tag(&self) -> &'static str {
+        LOCALES.iter()
+            .find(|&&(_, l)| l == *self)
+            .map(|&(tag, _)| tag)
+            .expect("Every locale has a tag")
+    }
+
+    /// Parse a BCP-47 tag, returning `None` when no supported language
+    /// matches it.
+    ///
+    /// `_` is accepted in place of `-` and case is ignored. When the full tag
+    /// isn't supported, subtags are dropped from the end until one is: the
+    /// fallback chain for `zh-Hant-TW` is `zh-Hant-TW`, `zh-Hant` then `zh`.
+    pub fn from_tag(tag: &str) -> Option<Locale> {
+        let tag = tag.trim().replace('_', "-");
+        let mut tag = tag.as_str();
+
+        loop {
+            let found = LOCALES.iter().chain(ALIASES)
+                .find(|&&(t, _)| t.eq_ignore_ascii_case(tag))
+                .map(|&(_, l)| l);
+            if found.is_some() {
+                return found;
+            }
+
+            match tag.rfind('-') {
+                Some(i) => tag = &tag[..i],
+                None => return None,
+            }
+        }
+    }
+
+    /// The first of `tags` with a supported language, such as from an
+    /// `Accept-Language` header in order of preference.
+    pub fn negotiate<'a, I: IntoIterator<Item = &'a str>>(tags: I) -> Option<Locale> {
+        tags.into_iter().filter_map(Locale::from_tag).next()
+    }
+
+    /// The language most widely spoken for a calling country code, when
+    /// there is a clear one.
+    pub fn from_country_code(country_code: u16) -> Option<Locale> {
+        COUNTRY_CODES.iter()
+            .find(|&&(c, _)| c == country_code)
+            .map(|&(_, l)| l)
+    }
+}
+
+impl Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.tag())
+    }
+}
+
+impl FromStr for Locale {
+    type Err = AuthyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Locale::from_tag(s).ok_or_else(|| AuthyError::InvalidRequest(format!("Unsupported locale: {}", s)))
+    }
+}
+
+impl TryFrom<String> for Locale {
+    type Error = AuthyError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Locale> for String {
+    fn from(l: Locale) -> String {
+        l.tag().into()
+    }
+}
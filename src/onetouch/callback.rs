@@ -0,0 +1,176 @@
+//! Verifying and parsing the results Authy sends to your OneTouch callback url.
+//!
+//! When a user approves or denies a request, Authy sends the result to the
+//! callback url configured for your application. Each callback is signed
+//! with your API key; the signature is sent in the `X-Authy-Signature`
+//! header and the nonce it was made with in `X-Authy-Signature-Nonce`.
+//!
+//! Nothing here depends on a particular web framework. Pass in the method,
+//! full url and parameters of the request as you received them:
+//!
+//! ```rust,ignore
+//! use authy::onetouch::callback;
+//!
+//! let params: Value = serde_json::from_slice(&body)?;
+//! callback::verify(api_key, "POST", &url, &params, nonce, signature)?;
+//!
+//! let event = callback::parse(&body)?;
+//! if event.status == ApprovalStatus::Approved {
+//!     // Log the user in.
+//! }
+//! ```
+//!
+//! Please see the Authy documentation for more details:
+//! https://www.twilio.com/docs/authy/api/push-authentications#verify-the-signature
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use base64;
+use hmac::{Hmac, Mac};
+use serde_json::{self, Value};
+use sha2::Sha256;
+
+use error::AuthyError;
+use de;
+use form::Form;
+use api::onetouch::ApprovalStatus;
+pub use api::onetouch::ApprovalTransaction;
+
+/// How far a callback's nonce may be from the current time before it is
+/// rejected as a replay.
+pub const REPLAY_WINDOW: Duration = Duration::from_secs(300);
+
+/// The result of an approval request, as sent to your callback url.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ApprovalEvent {
+    /// Identifies the approval request.
+    pub uuid: String,
+
+    pub status: ApprovalStatus,
+
+    /// The authy id of the user the request was sent to.
+    #[serde(deserialize_with = "de::u32")]
+    pub authy_id: u32,
+
+    /// Such as `approval_request_status`.
+    #[serde(default)]
+    pub callback_action: String,
+
+    pub device_uuid: Option<String>,
+
+    /// Signed by the user's device, when the request was encrypted.
+    pub signature: Option<String>,
+
+    pub approval_request: Option<ApprovalEventRequest>,
+
+    /// Any fields returned by Authy that aren't covered above.
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// The approval request included with an `ApprovalEvent`.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ApprovalEventRequest {
+    pub transaction: ApprovalTransaction,
+
+    /// Unix timestamp of when the request expires.
+    #[serde(default, deserialize_with = "de::option_u64")]
+    pub expiration_timestamp: Option<u64>,
+
+    /// Any fields returned by Authy that aren't covered above.
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// Verify a callback from Authy.
+///
+/// Checks that `signature` was made from the request with your API key, and
+/// that `nonce` is within `REPLAY_WINDOW` of the current time. `method` and
+/// `url` are those the callback was received with, and `params` holds its
+/// parameters: the json body, or the query string for a `GET`.
+///
+/// Returns `AuthyError::InvalidSignature` when either check fails. To reject
+/// every replay, rather than only old ones, also remember the nonces you have
+/// seen within the window.
+pub fn verify(api_key: &str, method: &str, url: &str, params: &Value, nonce: &str, signature: &str) -> Result<(), AuthyError> {
+    verify_nonce(nonce, SystemTime::now(), REPLAY_WINDOW)?;
+    verify_signature(api_key, method, url, params, nonce, signature)
+}
+
+/// Checks only the signature of a callback, comparing it in constant time.
+///
+/// Please see `verify`.
+pub fn verify_signature(api_key: &str, method: &str, url: &str, params: &Value, nonce: &str, signature: &str) -> Result<(), AuthyError> {
+    let signature = base64::decode(signature.trim())
+        .map_err(|_| AuthyError::InvalidSignature("Signature is not base64".into()))?;
+
+    mac(api_key, method, url, params, nonce)
+        .verify_slice(&signature)
+        .map_err(|_| AuthyError::InvalidSignature("Signature does not match".into()))
+}
+
+/// Checks only that `nonce` is within `window` of `now`.
+///
+/// Please see `verify`.
+pub fn verify_nonce(nonce: &str, now: SystemTime, window: Duration) -> Result<(), AuthyError> {
+    let nonce: f64 = nonce.trim().parse()
+        .ok()
+        .filter(|n: &f64| n.is_finite() && *n >= 0.0)
+        .ok_or_else(|| AuthyError::InvalidSignature(format!("Invalid nonce: {}", nonce)))?;
+
+    let now = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64();
+    if (now - nonce).abs() > window.as_secs_f64() {
+        return Err(AuthyError::InvalidSignature("Nonce is outside of the replay window".into()));
+    }
+
+    Ok(())
+}
+
+/// The signature Authy sends for a callback, base64 encoded.
+pub fn sign(api_key: &str, method: &str, url: &str, params: &Value, nonce: &str) -> String {
+    base64::encode(mac(api_key, method, url, params, nonce).finalize().into_bytes())
+}
+
+/// Parse the body of a callback into an `ApprovalEvent`.
+///
+/// The body should be verified first.
+pub fn parse(body: &[u8]) -> Result<ApprovalEvent, AuthyError> {
+    Ok(serde_json::from_slice(body)?)
+}
+
+// Signs `{nonce}|{METHOD}|{url}|{params}`, with params sorted and encoded
+// the way Rails' `to_query` does.
+fn mac(api_key: &str, method: &str, url: &str, params: &Value, nonce: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(api_key.as_bytes())
+        .expect("HMAC accepts keys of any length");
+
+    let data = format!("{}|{}|{}|{}", nonce, method.to_uppercase(), url, query(params));
+    mac.update(data.as_bytes());
+    mac
+}
+
+fn query(params: &Value) -> String {
+    let mut form = Form::new();
+    for (k, v) in params.as_object().into_iter().flatten() {
+        form.nested(k, v);
+    }
+
+    let mut pairs: Vec<String> = form.params().iter()
+        .map(|(k, v)| format!("{}={}", escape(k), escape(v)))
+        .collect();
+    pairs.sort();
+    pairs.join("&")
+}
+
+// Escapes everything but `A-Za-z0-9_.-~`, with spaces as `+`.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'_' | b'.' | b'-' | b'~' => escaped.push(b as char),
+            b' ' => escaped.push('+'),
+            b => escaped.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,226 @@
+//! The 'high-level' bindings to the Authy OneTouch API.
+//!
+//! Please see [api::onetouch](../api/onetouch/index.html) for more details.
+//! Results sent to your callback url are handled by [callback](callback/index.html),
+//! and requests that must be approved exactly as sent by `Transaction`.
+
+use std::cmp;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use error::AuthyError;
+use client::Client;
+use api;
+pub use api::onetouch::{ApprovalRequest, ApprovalRequestBuilder, ApprovalStatus, ApprovalTransaction, LogoResolution};
+
+pub mod callback;
+
+mod transaction;
+pub use self::transaction::{Transaction, DIGEST_KEY};
+
+/// How an approval request was resolved by `OneTouch::wait_for_approval`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ApprovalOutcome {
+    /// The user approved the request.
+    Approved,
+
+    /// The user denied the request.
+    Denied,
+
+    /// The request expired before the user responded.
+    Expired,
+
+    /// The caller's timeout passed while the request was still pending.
+    TimedOut,
+
+    /// Waiting was cancelled through a `Cancel` handle.
+    Cancelled,
+}
+
+/// Cancels a `OneTouch::wait_for_approval` from another thread.
+///
+/// Clones share the same state, so cancelling any of them stops the wait
+/// they were given to. A waiting thread wakes up immediately rather than at
+/// its next poll.
+#[derive(Debug, Clone, Default)]
+pub struct Cancel {
+    inner: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl Cancel {
+    pub fn new() -> Cancel {
+        Cancel::default()
+    }
+
+    /// Stop waiting. Any wait using this handle, now or later, returns
+    /// `ApprovalOutcome::Cancelled`.
+    pub fn cancel(&self) {
+        let (ref cancelled, ref cvar) = *self.inner;
+        *cancelled.lock().unwrap_or_else(|e| e.into_inner()) = true;
+        cvar.notify_all();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.inner.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // Sleeps for up to `timeout`, returning true if cancelled in the meantime.
+    fn sleep(&self, timeout: Duration) -> bool {
+        let (ref cancelled, ref cvar) = *self.inner;
+        let guard = cancelled.lock().unwrap_or_else(|e| e.into_inner());
+        let (guard, _) = cvar.wait_timeout_while(guard, timeout, |cancelled| !*cancelled)
+            .unwrap_or_else(|e| e.into_inner());
+        *guard
+    }
+}
+
+/// The shortest interval `OneTouch::wait_for_approval` polls at.
+pub const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Controls how `OneTouch::wait_for_approval` polls for the request's status.
+///
+/// The status is polled every `interval` to start with, doubling after each
+/// poll up to `max_interval`. Intervals shorter than `MIN_INTERVAL` are
+/// raised to it.
+#[derive(Debug, Clone)]
+pub struct WaitOptions {
+    /// Give up after this long, returning `ApprovalOutcome::TimedOut`. When
+    /// `None`, wait until Authy expires the request.
+    pub timeout: Option<Duration>,
+
+    pub interval: Duration,
+    pub max_interval: Duration,
+
+    pub cancel: Option<Cancel>,
+}
+
+impl WaitOptions {
+    /// Check the options, returning `AuthyError::InvalidRequest` when
+    /// `max_interval` is shorter than `interval`.
+    pub fn validate(&self) -> Result<(), AuthyError> {
+        if self.max_interval < self.interval {
+            return Err(AuthyError::InvalidRequest("Max interval must not be shorter than interval".into()));
+        }
+        Ok(())
+    }
+}
+
+impl Default for WaitOptions {
+    fn default() -> WaitOptions {
+        WaitOptions {
+            timeout: None,
+            interval: Duration::from_secs(1),
+            max_interval: Duration::from_secs(10),
+            cancel: None,
+        }
+    }
+}
+
+/// A OneTouch approval request as part of the 'high level' Authy interface.
+///
+/// Please see [api::onetouch](../api/onetouch/index.html) for more details.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct OneTouch {
+    /// Identifies the approval request.
+    pub uuid: String,
+
+    /// The latest status of the request.
+    pub status: ApprovalStatus,
+
+    /// The request as last returned by Authy.
+    pub request: ApprovalRequest,
+}
+
+impl OneTouch {
+    /// Send a new approval request to the user with the given authy id.
+    ///
+    /// Please see [api::onetouch::create](../api/onetouch/fn.create.html)
+    pub fn create(c: &Client, id: u32, request: &ApprovalRequestBuilder) -> Result<OneTouch, AuthyError> {
+        let (status, request) = api::onetouch::create(c, id, request)?;
+        status.check()?;
+
+        Ok(OneTouch {
+            uuid: request.uuid.clone(),
+            status: request.status.unwrap_or_default(),
+            request,
+        })
+    }
+
+    /// Returns the approval request identified by `uuid`.
+    ///
+    /// Please see [api::onetouch::status](../api/onetouch/fn.status.html)
+    pub fn find(c: &Client, uuid: &str) -> Result<OneTouch, AuthyError> {
+        let mut o = OneTouch { uuid: uuid.into(), ..OneTouch::default() };
+        o.update(c)?;
+        Ok(o)
+    }
+
+    /// Updates the `OneTouch` with the latest status from authy's service.
+    ///
+    /// Please see [api::onetouch::status](../api/onetouch/fn.status.html)
+    pub fn update(&mut self, c: &Client) -> Result<(), AuthyError> {
+        let (status, request) = api::onetouch::status(c, &self.uuid)?;
+        status.check()?;
+
+        self.status = request.status.unwrap_or_default();
+        self.request = request;
+
+        Ok(())
+    }
+
+    /// Blocks until the user approves or denies the request, it expires,
+    /// the timeout passes or the wait is cancelled.
+    ///
+    /// Errors from polling Authy are returned as they happen; the request
+    /// may still be pending and can be waited on again.
+    pub fn wait_for_approval(&mut self, c: &Client, options: &WaitOptions) -> Result<ApprovalOutcome, AuthyError> {
+        self.wait_with(options, |o| o.update(c))
+    }
+
+    /// Like `wait_for_approval`, but calls `poll` to bring the `OneTouch` up
+    /// to date rather than asking Authy, such as to wait on statuses received
+    /// by your callback url.
+    pub fn wait_with<F>(&mut self, options: &WaitOptions, mut poll: F) -> Result<ApprovalOutcome, AuthyError>
+        where F: FnMut(&mut OneTouch) -> Result<(), AuthyError>,
+    {
+        options.validate()?;
+
+        let deadline = options.timeout.map(|t| Instant::now() + t);
+        let max_interval = cmp::max(options.max_interval, MIN_INTERVAL);
+        let mut interval = cmp::max(options.interval, MIN_INTERVAL);
+
+        loop {
+            if options.cancel.as_ref().is_some_and(Cancel::is_cancelled) {
+                return Ok(ApprovalOutcome::Cancelled);
+            }
+
+            poll(self)?;
+            match self.status {
+                ApprovalStatus::Approved => return Ok(ApprovalOutcome::Approved),
+                ApprovalStatus::Denied => return Ok(ApprovalOutcome::Denied),
+                ApprovalStatus::Expired => return Ok(ApprovalOutcome::Expired),
+                ApprovalStatus::Pending | ApprovalStatus::Unknown => (),
+            }
+
+            let mut wait = interval;
+            if let Some(deadline) = deadline {
+                let now = Instant::now();
+                if now >= deadline {
+                    return Ok(ApprovalOutcome::TimedOut);
+                }
+                wait = cmp::min(wait, deadline - now);
+            }
+
+            let cancelled = match options.cancel {
+                Some(ref cancel) => cancel.sleep(wait),
+                None => { thread::sleep(wait); false },
+            };
+            if cancelled {
+                return Ok(ApprovalOutcome::Cancelled);
+            }
+
+            interval = cmp::min(interval * 2, max_interval);
+        }
+    }
+}
@@ -0,0 +1,196 @@
+//! Approval requests for transactions, such as money transfers, that must be
+//! approved exactly as they were sent.
+//!
+//! A `Transaction` holds the details shown to the user. When it is turned
+//! into an approval request, a digest of the details, keyed with a secret
+//! only your servers know, is added as a hidden detail. Once Authy reports
+//! the result, `verify_request` or `verify_event` checks that the details it
+//! echoes back are the ones that were sent, and that the digest was made with
+//! your key:
+//!
+//! ```rust,ignore
+//! let transfer = Transaction::new("Approve transfer to Jane Doe")
+//!     .detail("Amount", "$1,000.00")
+//!     .detail("Recipient", "Jane Doe")
+//!     .hidden_detail("account", "12345678");
+//!
+//! let mut request = OneTouch::create(&c, user.id, &transfer.request(&key).reason("Transfer"))?;
+//!
+//! // Later, in the callback handler:
+//! let event = callback::parse(&body)?;
+//! transfer.verify_event(&event, &key)?;
+//! ```
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+use serde_json::{self, Value};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use error::AuthyError;
+use api::onetouch::{ApprovalRequest, ApprovalRequestBuilder};
+use onetouch::callback::ApprovalEvent;
+
+/// The hidden detail holding a transaction's digest.
+pub const DIGEST_KEY: &str = "transaction_digest";
+
+/// The message and details of a transaction to be approved.
+///
+/// Details are kept sorted by key, so a transaction always serializes, and
+/// digests, the same way regardless of the order they were added in.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Transaction {
+    pub message: String,
+    pub details: BTreeMap<String, String>,
+    pub hidden_details: BTreeMap<String, String>,
+}
+
+impl Transaction {
+    /// Start a transaction showing the user `message`.
+    pub fn new(message: &str) -> Transaction {
+        Transaction {
+            message: message.into(),
+            ..Transaction::default()
+        }
+    }
+
+    /// Build a transaction from the fields of `details`, which must serialize
+    /// to a json object, such as a struct. Strings are used as they are,
+    /// `null`s are skipped and any other values are sent as json.
+    pub fn from_details<T: Serialize>(message: &str, details: &T) -> Result<Transaction, AuthyError> {
+        let mut transaction = Transaction::new(message);
+
+        match serde_json::to_value(details)? {
+            Value::Object(o) => for (k, v) in o {
+                match v {
+                    Value::Null => (),
+                    Value::String(s) => { transaction.details.insert(k, s); },
+                    other => { transaction.details.insert(k, other.to_string()); },
+                }
+            },
+            _ => return Err(AuthyError::InvalidRequest("Transaction details must be an object".into())),
+        }
+
+        Ok(transaction)
+    }
+
+    /// Add a detail shown to the user.
+    pub fn detail(mut self, key: &str, value: &str) -> Transaction {
+        self.details.insert(key.into(), value.into());
+        self
+    }
+
+    /// Add a detail that is stored with the request but not shown to the user.
+    pub fn hidden_detail(mut self, key: &str, value: &str) -> Transaction {
+        self.hidden_details.insert(key.into(), value.into());
+        self
+    }
+
+    /// Hex encoded HMAC-SHA256 of the message and details, keyed with `key`.
+    ///
+    /// `key` must be a secret kept by your servers, such as your API key, so
+    /// that whoever can change the details can't make a matching digest. Each
+    /// field is length prefixed, so no two different transactions share an
+    /// encoding.
+    pub fn digest(&self, key: &[u8]) -> String {
+        self.mac(key).finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// An approval request for the transaction, with its digest keyed with
+    /// `key` added to the hidden details. Logos, a reason and an expiry can be
+    /// added before it is sent.
+    pub fn request(&self, key: &[u8]) -> ApprovalRequestBuilder {
+        let mut request = ApprovalRequestBuilder::new(&self.message);
+        for (k, v) in &self.details {
+            request = request.detail(k, v);
+        }
+        for (k, v) in self.hidden_details.iter().filter(|(k, _)| *k != DIGEST_KEY) {
+            request = request.hidden_detail(k, v);
+        }
+        request.hidden_detail(DIGEST_KEY, &self.digest(key))
+    }
+
+    /// Check the details echoed back by Authy against the transaction.
+    ///
+    /// Returns `AuthyError::TransactionMismatch` naming the first difference
+    /// found, or when the digest is missing or wasn't made with `key`. The
+    /// digest is compared in constant time.
+    pub fn verify(&self, message: Option<&str>, details: &HashMap<String, String>, hidden_details: &HashMap<String, String>, key: &[u8]) -> Result<(), AuthyError> {
+        if let Some(message) = message {
+            if message != self.message {
+                return Err(AuthyError::TransactionMismatch("message".into()));
+            }
+        }
+
+        compare("details", &self.details, details)?;
+        compare("hidden_details", &self.hidden_details, hidden_details)?;
+
+        match hidden_details.get(DIGEST_KEY) {
+            Some(digest) if hex_decode(digest).is_some_and(|d| self.mac(key).verify_slice(&d).is_ok()) => Ok(()),
+            Some(_) => Err(AuthyError::TransactionMismatch(format!("hidden_details[{}]", DIGEST_KEY))),
+            None => Err(AuthyError::TransactionMismatch(format!("hidden_details[{}] is missing", DIGEST_KEY))),
+        }
+    }
+
+    /// Check an approval request returned by `api::onetouch::status`.
+    pub fn verify_request(&self, request: &ApprovalRequest, key: &[u8]) -> Result<(), AuthyError> {
+        let transaction = match request.transaction {
+            Some(ref transaction) => transaction,
+            None => return Err(AuthyError::TransactionMismatch("transaction is missing".into())),
+        };
+
+        self.verify(Some(&transaction.message), &transaction.details, &transaction.hidden_details, key)
+    }
+
+    /// Check an approval result sent to your callback url.
+    pub fn verify_event(&self, event: &ApprovalEvent, key: &[u8]) -> Result<(), AuthyError> {
+        let transaction = match event.approval_request {
+            Some(ref request) => &request.transaction,
+            None => return Err(AuthyError::TransactionMismatch("approval_request is missing".into())),
+        };
+
+        self.verify(Some(&transaction.message), &transaction.details, &transaction.hidden_details, key)
+    }
+
+    fn mac(&self, key: &[u8]) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key)
+            .expect("HMAC accepts keys of any length");
+        write_field(&mut mac, "message", &self.message);
+        for (k, v) in &self.details {
+            write_field(&mut mac, "details", k);
+            write_field(&mut mac, "", v);
+        }
+        for (k, v) in self.hidden_details.iter().filter(|(k, _)| *k != DIGEST_KEY) {
+            write_field(&mut mac, "hidden_details", k);
+            write_field(&mut mac, "", v);
+        }
+        mac
+    }
+}
+
+fn write_field(mac: &mut Hmac<Sha256>, name: &str, value: &str) {
+    mac.update(format!("{}:{}:", name, value.len()).as_bytes());
+    mac.update(value.as_bytes());
+}
+
+fn hex_decode(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 != 0 {
+        return None;
+    }
+    (0..s.len()).step_by(2).map(|i| s.get(i..i + 2).and_then(|b| u8::from_str_radix(b, 16).ok())).collect()
+}
+
+fn compare(name: &str, expected: &BTreeMap<String, String>, echoed: &HashMap<String, String>) -> Result<(), AuthyError> {
+    for (k, v) in echoed.iter().filter(|(k, _)| *k != DIGEST_KEY) {
+        if expected.get(k) != Some(v) {
+            return Err(AuthyError::TransactionMismatch(format!("{}[{}]", name, k)));
+        }
+    }
+    for k in expected.keys().filter(|k| *k != DIGEST_KEY) {
+        if !echoed.contains_key(k) {
+            return Err(AuthyError::TransactionMismatch(format!("{}[{}] is missing", name, k)));
+        }
+    }
+
+    Ok(())
+}
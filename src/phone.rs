@@ -5,7 +5,26 @@
 use error::AuthyError;
 use client::Client;
 use api;
-pub use api::phone::{ContactType, PhoneStart};
+pub use api::phone::{ContactType, PhoneStart, PhoneStartOptions, PhoneVerificationStatus, VerificationStatus};
+use api::phone::{Verification, ERROR_WRONG_CODE, ERROR_NO_PENDING_VERIFICATION};
+
+/// How a code was judged by `Phone::check`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum PhoneCheckOutcome {
+    /// The code was correct and the phone number is verified.
+    Verified,
+
+    /// The code was incorrect. The verification is still pending, so the
+    /// user can try again.
+    WrongCode,
+
+    /// The verification expired before the code was checked.
+    Expired,
+
+    /// No verification was started for the phone number, or it was already
+    /// checked.
+    NoPendingVerification,
+}
 
 /// Returned when finding a phone number.
 ///
@@ -25,7 +44,7 @@ impl Phone {
     /// Please see [api::phone::info](../api/phone/fn.info.html) for more details.
     pub fn find(c: &Client, country_code: u16, phone: &str) -> Result<Phone, AuthyError> {
         let (status, info) = api::phone::info(c, country_code, phone, None)?;
-        assert!(status.success);
+        status.check()?;
 
         Ok(Phone {
             country_code: country_code,
@@ -39,20 +58,46 @@ impl Phone {
     /// Initiate a phone verification check.
     ///
     /// Please see [api::phone::start](../api/phone/fn.start.html) for more details.
-    pub fn start(&self, c: &Client, via: ContactType, code_length: Option<u8>, locale: Option<&str>) -> Result<PhoneStart, AuthyError> {
-        let (status, phone_start) = api::phone::start(c, via, self.country_code, &self.phone_number, code_length, locale)?;
-        assert!(status.success);
+    pub fn start(&self, c: &Client, via: ContactType, options: &PhoneStartOptions) -> Result<PhoneStart, AuthyError> {
+        let (status, phone_start) = api::phone::start(c, via, self.country_code, &self.phone_number, options)?;
+        status.check()?;
 
         Ok(phone_start)
     }
 
     /// Verify phone verification code sent to user.
     ///
+    /// When Authy has no pending verification for the number, its status is
+    /// looked up to tell an expired verification from a missing one. Errors
+    /// from that lookup are returned as they are.
+    ///
     /// Please see [api::phone::check](../api/phone/fn.check.html) for more details.
-    pub fn check(&self, c: &Client, code: &str) -> Result<(), AuthyError> {
-        let status = api::phone::check(c, self.country_code, &self.phone_number, code)?;
-        assert!(status.success);
+    pub fn check(&self, c: &Client, code: &str) -> Result<PhoneCheckOutcome, AuthyError> {
+        match api::phone::check(c, self.country_code, &self.phone_number, code) {
+            Ok(status) => {
+                status.check()?;
+                Ok(PhoneCheckOutcome::Verified)
+            },
+            Err(ref e) if e.error_code() == Some(ERROR_WRONG_CODE) => Ok(PhoneCheckOutcome::WrongCode),
+            Err(ref e) if e.error_code() == Some(ERROR_NO_PENDING_VERIFICATION) => {
+                match self.status(c) {
+                    Ok(ref s) if s.status == VerificationStatus::Expired => Ok(PhoneCheckOutcome::Expired),
+                    Ok(_) => Ok(PhoneCheckOutcome::NoPendingVerification),
+                    Err(e) => Err(e),
+                }
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Status of the latest verification started for the phone number.
+    ///
+    /// Please see [api::phone::status](../api/phone/fn.status.html) for more details.
+    pub fn status(&self, c: &Client) -> Result<PhoneVerificationStatus, AuthyError> {
+        let verification = Verification::Number { country_code: self.country_code, phone: &self.phone_number };
+        let (status, verification_status) = api::phone::status(c, verification)?;
+        status.check()?;
 
-        Ok(())
+        Ok(verification_status)
     }
 }
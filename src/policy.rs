@@ -0,0 +1,191 @@
+//! Deciding whether to trust a phone number, using the Phone Intelligence
+//! API.
+//!
+//! Please see [phone](../phone/index.html) for looking up a `Phone`.
+
+use std::fmt::{self, Display};
+use std::fs;
+use std::path::Path;
+
+use toml;
+
+use error::AuthyError;
+use phone::{Phone, ContactType};
+
+/// What to do when a rule matches.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Ask for further verification, such as a manual review.
+    StepUp,
+
+    /// Refuse the phone number.
+    Deny,
+}
+
+/// The outcome of `PhonePolicy::evaluate`, from least to most severe.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    Allow,
+    StepUp,
+    Deny,
+}
+
+impl From<Action> for Decision {
+    fn from(a: Action) -> Decision {
+        match a {
+            Action::StepUp => Decision::StepUp,
+            Action::Deny => Decision::Deny,
+        }
+    }
+}
+
+/// Why a rule matched.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reason {
+    /// The country code is blocked.
+    BlockedCountry(u16),
+
+    /// The number is voip.
+    Voip,
+
+    /// The number is a landline, which can't receive SMS.
+    LandlineForSms,
+
+    /// Authy doesn't know the type of the number.
+    UnknownType,
+
+    /// The number was ported, and its current provider isn't allowed.
+    Ported(Option<String>),
+}
+
+impl Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Reason::BlockedCountry(c) => write!(f, "country code {} is blocked", c),
+            Reason::Voip => write!(f, "voip numbers are not accepted"),
+            Reason::LandlineForSms => write!(f, "landlines can't receive SMS"),
+            Reason::UnknownType => write!(f, "the type of the number is unknown"),
+            Reason::Ported(Some(ref p)) => write!(f, "ported number now with {}", p),
+            Reason::Ported(None) => write!(f, "ported number now with an unknown provider"),
+        }
+    }
+}
+
+/// Returned by `PhonePolicy::evaluate`.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Evaluation {
+    /// The most severe action of the rules that matched, or `Allow` when none
+    /// did.
+    pub decision: Decision,
+
+    /// Every rule that matched, along with its action.
+    pub reasons: Vec<(Reason, Action)>,
+}
+
+impl Evaluation {
+    pub fn is_allowed(&self) -> bool {
+        self.decision == Decision::Allow
+    }
+}
+
+/// Rules for the phone numbers to accept.
+///
+/// Each rule is off unless it is given an action. A policy can be built in
+/// code or loaded from TOML, where unknown keys are rejected so that a
+/// misspelled rule doesn't silently allow everything:
+///
+/// ```toml
+/// voip = "deny"
+/// landline_for_sms = "deny"
+/// unknown_type = "step_up"
+/// ported = "step_up"
+/// allowed_ported_providers = ["Verizon Wireless"]
+/// blocked_country_codes = [7, 86]
+/// ```
+///
+/// Example:
+///
+/// ```rust,ignore
+/// let policy = PhonePolicy::from_file("phone_policy.toml")?;
+/// let phone = Phone::find(&c, country_code, phone_number)?;
+///
+/// let evaluation = policy.evaluate(&phone, ContactType::SMS);
+/// if !evaluation.is_allowed() {
+///     for (reason, action) in &evaluation.reasons {
+///         println!("{:?}: {}", action, reason);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PhonePolicy {
+    /// For voip numbers.
+    pub voip: Option<Action>,
+
+    /// For landlines, when the code would be sent over SMS.
+    pub landline_for_sms: Option<Action>,
+
+    /// For numbers whose type Authy doesn't know.
+    pub unknown_type: Option<Action>,
+
+    /// For ported numbers whose current provider isn't in
+    /// `allowed_ported_providers`.
+    pub ported: Option<Action>,
+
+    /// Providers that ported numbers are accepted with, compared ignoring
+    /// case. These are compared with the provider the number is with now, as
+    /// Authy doesn't report the one it was ported from.
+    pub allowed_ported_providers: Vec<String>,
+
+    /// Country codes that are always denied.
+    pub blocked_country_codes: Vec<u16>,
+}
+
+impl PhonePolicy {
+    /// Parse a policy from TOML.
+    pub fn from_toml(s: &str) -> Result<PhonePolicy, AuthyError> {
+        toml::from_str(s).map_err(|e| AuthyError::ConfigError(e.to_string()))
+    }
+
+    /// Load a policy from a TOML file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<PhonePolicy, AuthyError> {
+        PhonePolicy::from_toml(&fs::read_to_string(path)?)
+    }
+
+    /// Evaluate `phone`, to which a code would be sent `via`.
+    pub fn evaluate(&self, phone: &Phone, via: ContactType) -> Evaluation {
+        let mut reasons = vec![];
+
+        if self.blocked_country_codes.contains(&phone.country_code) {
+            reasons.push((Reason::BlockedCountry(phone.country_code), Action::Deny));
+        }
+
+        let phone_type = phone.phone_type.to_lowercase();
+        let rule = match phone_type.as_str() {
+            "voip" => self.voip.map(|a| (Reason::Voip, a)),
+            "landline" if via == ContactType::SMS => self.landline_for_sms.map(|a| (Reason::LandlineForSms, a)),
+            "cellphone" | "landline" => None,
+            _ => self.unknown_type.map(|a| (Reason::UnknownType, a)),
+        };
+        reasons.extend(rule);
+
+        if phone.ported {
+            let allowed = phone.provider.as_ref().is_some_and(|p| {
+                self.allowed_ported_providers.iter().any(|a| a.eq_ignore_ascii_case(p))
+            });
+            if !allowed {
+                reasons.extend(self.ported.map(|a| (Reason::Ported(phone.provider.clone()), a)));
+            }
+        }
+
+        let decision = reasons.iter()
+            .map(|&(_, a)| Decision::from(a))
+            .max()
+            .unwrap_or(Decision::Allow);
+
+        Evaluation { decision, reasons }
+    }
+}
@@ -0,0 +1,256 @@
+//! One-time recovery codes, for when the user has lost their phone or Authy
+//! can't be reached.
+//!
+//! Codes are shown to the user once, when generated. Only a salted
+//! PBKDF2-HMAC-SHA256 hash of each is kept, in a `Store`, so a leaked store
+//! is slow to guess codes from. Each code can be used a single time:
+//!
+//! ```rust,ignore
+//! let recovery = RecoveryCodes::new(store);
+//! for code in recovery.generate(user.id)? {
+//!     println!("{}", code);
+//! }
+//!
+//! // At login, the user can enter either a token or a recovery code:
+//! match user.verify_or_recover(&c, &token, &VerifyOptions::default(), &recovery)? {
+//!     VerifyOutcome::Valid => (),
+//!     other => (),
+//! }
+//! ```
+//!
+//! Codes look like `7kq4m-x2hvp`. They always contain a letter, so they can't
+//! be mistaken for a token.
+
+use std::sync::Arc;
+
+use base64;
+use getrandom;
+use hmac::Hmac;
+use pbkdf2;
+use serde_json;
+use sha2::Sha256;
+
+use error::AuthyError;
+use compare::constant_time_eq;
+use store::Store;
+use user::VerifyOutcome;
+
+/// The characters codes are made of. Those easily confused with each other,
+/// such as `0` and `o`, are left out.
+const ALPHABET: &[u8] = b"23456789abcdefghjkmnpqrstuvwxyz";
+
+/// The length of a code, without the separator.
+pub const CODE_LENGTH: usize = 10;
+
+const SALT_LENGTH: usize = 16;
+
+const HASH_LENGTH: usize = 32;
+
+/// The PBKDF2 rounds used by `RecoveryCodes::new`.
+pub const DEFAULT_ROUNDS: u32 = 10_000;
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+struct CodeSet {
+    /// Identifies this set, so codes used from an earlier one don't carry
+    /// over.
+    generation: String,
+
+    /// The PBKDF2 rounds the codes were hashed with.
+    rounds: u32,
+    codes: Vec<HashedCode>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+struct HashedCode {
+    salt: String,
+    hash: String,
+}
+
+/// Generates and checks the recovery codes of each user.
+#[derive(Clone)]
+pub struct RecoveryCodes {
+    /// How many codes `generate` makes.
+    pub count: usize,
+
+    /// The PBKDF2 rounds `generate` hashes codes with. Codes are checked with
+    /// the rounds they were generated with, so this can be raised at any
+    /// time. Each check of a code costs every one of the user's codes a hash.
+    pub rounds: u32,
+    store: Arc<dyn Store>,
+}
+
+impl RecoveryCodes {
+    /// Keep codes in `store`, generating 10 at a time.
+    pub fn new(store: Arc<dyn Store>) -> RecoveryCodes {
+        RecoveryCodes { count: 10, rounds: DEFAULT_ROUNDS, store }
+    }
+
+    pub fn count(mut self, count: usize) -> RecoveryCodes {
+        self.count = count;
+        self
+    }
+
+    pub fn rounds(mut self, rounds: u32) -> RecoveryCodes {
+        self.rounds = rounds;
+        self
+    }
+
+    /// Generate a new set of codes for user `id`, returning them to be shown
+    /// to the user. Any codes generated before no longer work.
+    pub fn generate(&self, id: u32) -> Result<Vec<String>, AuthyError> {
+        if self.rounds == 0 {
+            return Err(AuthyError::InvalidRequest("Rounds must be at least 1".into()));
+        }
+
+        let mut codes = vec![];
+        let mut set = CodeSet { generation: base64::encode(random(SALT_LENGTH)?), rounds: self.rounds, codes: vec![] };
+
+        while codes.len() < self.count {
+            let code = random_code()?;
+            let formatted = format!("{}-{}", &code[..CODE_LENGTH / 2], &code[CODE_LENGTH / 2..]);
+            if codes.contains(&formatted) {
+                continue;
+            }
+
+            let salt = random(SALT_LENGTH)?;
+            let hash = hash(&salt, &code, set.rounds);
+            set.codes.push(HashedCode { salt: base64::encode(&salt), hash: base64::encode(hash) });
+            codes.push(formatted);
+        }
+
+        // The new set replaces the old one in a single write, so there is no
+        // moment without codes, or with both. The old set's used markers
+        // are only cleaned up after.
+        let old = self.load(id)?;
+        self.store.set(&key(id), &serde_json::to_string(&set)?, None)?;
+        if let Some(old) = old {
+            self.remove_used(id, &old)?;
+        }
+
+        Ok(codes)
+    }
+
+    /// Check `code` for user `id`, using it up when it is valid.
+    ///
+    /// Returns `VerifyOutcome::Valid` or `VerifyOutcome::Invalid`, which
+    /// includes codes that were already used. Case, spaces and dashes are
+    /// ignored. Every code is checked in constant time, so timing doesn't
+    /// reveal how close a guess was.
+    pub fn verify(&self, id: u32, code: &str) -> Result<VerifyOutcome, AuthyError> {
+        let set = match self.load(id)? {
+            Some(set) => set,
+            None => return Ok(VerifyOutcome::Invalid),
+        };
+        let code = normalize(code);
+
+        let mut found = None;
+        for (i, hashed) in set.codes.iter().enumerate() {
+            let salt = base64::decode(&hashed.salt).map_err(|e| AuthyError::JsonParseError(e.to_string()))?;
+            let hash = base64::decode(&hashed.hash).map_err(|e| AuthyError::JsonParseError(e.to_string()))?;
+            if constant_time_eq(&self::hash(&salt, &code, set.rounds), &hash) {
+                found = Some(i);
+            }
+        }
+
+        match found {
+            Some(i) if self.store.add(&used_key(id, &set, i), "1", None)? => Ok(VerifyOutcome::Valid),
+            _ => Ok(VerifyOutcome::Invalid),
+        }
+    }
+
+    /// The number of codes user `id` has left.
+    pub fn remaining(&self, id: u32) -> Result<usize, AuthyError> {
+        let set = match self.load(id)? {
+            Some(set) => set,
+            None => return Ok(0),
+        };
+
+        let mut remaining = 0;
+        for i in 0..set.codes.len() {
+            if self.store.get(&used_key(id, &set, i))?.is_none() {
+                remaining += 1;
+            }
+        }
+        Ok(remaining)
+    }
+
+    /// Remove every code for user `id`.
+    pub fn revoke(&self, id: u32) -> Result<(), AuthyError> {
+        let set = self.load(id)?;
+        self.store.remove(&key(id))?;
+        match set {
+            Some(set) => self.remove_used(id, &set),
+            None => Ok(()),
+        }
+    }
+
+    // Only called once `set` is no longer stored, so used codes can't come
+    // back to life.
+    fn remove_used(&self, id: u32, set: &CodeSet) -> Result<(), AuthyError> {
+        for i in 0..set.codes.len() {
+            self.store.remove(&used_key(id, set, i))?;
+        }
+        Ok(())
+    }
+
+    fn load(&self, id: u32) -> Result<Option<CodeSet>, AuthyError> {
+        match self.store.get(&key(id))? {
+            Some(v) => Ok(Some(serde_json::from_str(&v)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// true when `token` has the form of a recovery code rather than a token,
+/// which is only ever digits.
+pub fn is_recovery_code(token: &str) -> bool {
+    let code = normalize(token);
+    code.len() == CODE_LENGTH && code.bytes().all(|b| ALPHABET.contains(&b)) && !code.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn key(id: u32) -> String {
+    format!("authy:recovery:{}", id)
+}
+
+fn used_key(id: u32, set: &CodeSet, i: usize) -> String {
+    format!("authy:recovery:{}:{}:{}", id, set.generation, i)
+}
+
+fn normalize(code: &str) -> String {
+    code.chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+fn hash(salt: &[u8], code: &str, rounds: u32) -> [u8; HASH_LENGTH] {
+    let mut hash = [0; HASH_LENGTH];
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(code.as_bytes(), salt, rounds, &mut hash);
+    hash
+}
+
+fn random(len: usize) -> Result<Vec<u8>, AuthyError> {
+    let mut bytes = vec![0; len];
+    getrandom::getrandom(&mut bytes).map_err(|e| AuthyError::IoError(e.to_string()))?;
+    Ok(bytes)
+}
+
+// Bytes past the largest multiple of the alphabet's length are skipped, so
+// every character is equally likely. Codes of only digits are skipped too.
+fn random_code() -> Result<String, AuthyError> {
+    let limit = 256 - 256 % ALPHABET.len();
+    loop {
+        let mut code = String::with_capacity(CODE_LENGTH);
+        while code.len() < CODE_LENGTH {
+            for b in random(CODE_LENGTH)? {
+                if (b as usize) < limit && code.len() < CODE_LENGTH {
+                    code.push(ALPHABET[b as usize % ALPHABET.len()] as char);
+                }
+            }
+        }
+
+        if is_recovery_code(&code) {
+            return Ok(code);
+        }
+    }
+}
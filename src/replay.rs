@@ -0,0 +1,96 @@
+//! Rejecting tokens that were already used.
+//!
+//! Authy accepts a token as many times as it is entered while it is valid, so
+//! a token seen over the user's shoulder can be used again. A `ReplayCache`
+//! remembers tokens that were accepted, and `User::verify_with_replay_cache`
+//! reports them as `VerifyOutcome::Replayed`:
+//!
+//! ```rust,ignore
+//! let cache = ReplayCache::new(Arc::new(FileStore::new("replay.json")));
+//!
+//! match user.verify_with_replay_cache(&c, &token, &VerifyOptions::default(), &cache)? {
+//!     VerifyOutcome::Valid => (),
+//!     VerifyOutcome::Replayed => println!("That token was already used"),
+//!     other => (),
+//! }
+//! ```
+//!
+//! The same cache can be used for tokens checked without Authy, such as with
+//! [totp](../totp/index.html), by passing the check to `verify`.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use sha2::{Digest, Sha256};
+
+use error::AuthyError;
+use store::Store;
+use user::VerifyOutcome;
+
+/// Remembers the tokens that verified for each user.
+#[derive(Clone)]
+pub struct ReplayCache {
+    /// How long a token is remembered for. It should be at least as long as
+    /// tokens are valid.
+    pub window: Duration,
+    store: Arc<dyn Store>,
+}
+
+impl ReplayCache {
+    /// Keep tokens in `store`, remembering them for 10 minutes.
+    pub fn new(store: Arc<dyn Store>) -> ReplayCache {
+        ReplayCache { window: Duration::from_secs(600), store }
+    }
+
+    pub fn window(mut self, window: Duration) -> ReplayCache {
+        self.window = window;
+        self
+    }
+
+    /// true when `token` already verified for user `id` within the window.
+    pub fn is_used(&self, id: u32, token: &str) -> Result<bool, AuthyError> {
+        Ok(self.store.get(&key(id, token))?.is_some())
+    }
+
+    /// Remember that `token` verified for user `id`.
+    ///
+    /// Returns false when it was already remembered, such as when the same
+    /// token was verified at the same time on another server.
+    pub fn record(&self, id: u32, token: &str) -> Result<bool, AuthyError> {
+        self.store.add(&key(id, token), "1", Some(self.window))
+    }
+
+    /// Check `token` for user `id` with `f`, unless it was already accepted.
+    ///
+    /// Tokens already remembered are reported as `VerifyOutcome::Replayed`
+    /// without calling `f`. Tokens `f` accepts are remembered, including
+    /// `VerifyOutcome::AcceptedUnconfirmed` ones, since Authy would accept
+    /// them again too. When the same token is accepted at the same time on
+    /// another server, only one of them reports it as accepted.
+    pub fn verify<F>(&self, id: u32, token: &str, f: F) -> Result<VerifyOutcome, AuthyError>
+        where F: FnOnce() -> Result<VerifyOutcome, AuthyError>,
+    {
+        if self.is_used(id, token)? {
+            return Ok(VerifyOutcome::Replayed);
+        }
+
+        match f()? {
+            VerifyOutcome::Valid | VerifyOutcome::AcceptedUnconfirmed if !self.record(id, token)? => Ok(VerifyOutcome::Replayed),
+            outcome => Ok(outcome),
+        }
+    }
+}
+
+impl fmt::Debug for ReplayCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ReplayCache").field("window", &self.window).finish()
+    }
+}
+
+// Only a hash of the token is kept, keyed by the user so the same token for
+// two users is two entries.
+fn key(id: u32, token: &str) -> String {
+    let hash = Sha256::digest(format!("{}:{}", id, token.trim()));
+    format!("authy:replay:{}:{}", id, hash.iter().map(|b| format!("{:02x}", b)).collect::<String>())
+}
@@ -0,0 +1,296 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, Deserialize, DeserializeOwned, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, Visitor};
+use serde::de::value::{BorrowedStrDeserializer, StringDeserializer};
+use serde::Serialize;
+use serde_json::{self, Value};
+
+use error::AuthyError;
+use client::Status;
+use de as lenient;
+
+/// Implemented by every type the Authy service hands back to us.
+///
+/// Response types capture any fields they don't know about in an `extra` map
+/// so that new fields added by Authy are not lost. When the client is
+/// configured to be `lenient`, fields missing from a response are filled in
+/// from `Default` rather than failing the whole call, with the exception of
+/// the fields listed in `REQUIRED`.
+pub trait Response: DeserializeOwned + Serialize + Default {
+    /// Fields that must always be present, even for a lenient client.
+    const REQUIRED: &'static [&'static str] = &[];
+
+    /// Deserialize `value`, defaulting any missing or null fields that are
+    /// not listed in `REQUIRED`.
+    fn from_value_lenient(mut value: Value) -> Result<Self, AuthyError> {
+        if let Value::Object(ref mut fields) = value {
+            for key in Self::REQUIRED {
+                if fields.get(*key).map_or(true, Value::is_null) {
+                    return Err(AuthyError::JsonParseError(format!("missing field `{}`", key)));
+                }
+            }
+
+            if let Value::Object(defaults) = serde_json::to_value(Self::default())? {
+                for (key, default) in defaults {
+                    let missing = fields.get(&key).map_or(true, Value::is_null);
+                    if missing {
+                        fields.insert(key, default);
+                    }
+                }
+            }
+        }
+
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+impl Response for Value {}
+
+impl<T: Response> Response for Vec<T> {
+    fn from_value_lenient(value: Value) -> Result<Self, AuthyError> {
+        match value {
+            Value::Array(items) => items.into_iter().map(T::from_value_lenient).collect(),
+            Value::Null => Ok(vec![]),
+            other => Ok(serde_json::from_value(other)?),
+        }
+    }
+}
+
+/// The payload of an endpoint that returns nothing beyond its `Status`.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize)]
+pub struct Empty;
+
+impl<'de> Deserialize<'de> for Empty {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Empty, D::Error> {
+        IgnoredAny::deserialize(d).map(|_| Empty)
+    }
+}
+
+impl Response for Empty {}
+
+/// Decode the `Status` and payload of a response straight from its body.
+///
+/// Both are read in a single pass. When the payload lives under `key`, any
+/// other fields are skipped without being allocated. Otherwise the payload is
+/// the body itself, and the `Status` fields are picked out as the payload
+/// reads them.
+pub fn decode<T: DeserializeOwned>(body: &[u8], key: Option<&str>) -> serde_json::Result<(Status, T)> {
+    let mut d = serde_json::Deserializer::from_slice(body);
+    let decoded = match key {
+        Some(key) => Envelope { key, payload: PhantomData }.deserialize(&mut d)?,
+        None => {
+            let mut status = Status { success: false, message: String::new(), error_code: None };
+            let payload = T::deserialize(WholeBody { inner: &mut d, status: &mut status })?;
+            (status, payload)
+        },
+    };
+    d.end()?;
+    Ok(decoded)
+}
+
+struct Envelope<'a, T> {
+    key: &'a str,
+    payload: PhantomData<T>,
+}
+
+impl<'de, 'a, T: Deserialize<'de>> DeserializeSeed<'de> for Envelope<'a, T> {
+    type Value = (Status, T);
+
+    fn deserialize<D: Deserializer<'de>>(self, d: D) -> Result<Self::Value, D::Error> {
+        d.deserialize_map(self)
+    }
+}
+
+impl<'de, 'a, T: Deserialize<'de>> Visitor<'de> for Envelope<'a, T> {
+    type Value = (Status, T);
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an Authy response containing `{}`", self.key)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut status = Status { success: false, message: String::new(), error_code: None };
+        let mut payload = None;
+
+        while let Some(field) = map.next_key_seed(FieldSeed(self.key))? {
+            match field {
+                Field::Success => status.success = map.next_value::<Success>()?.0,
+                Field::Message => status.message = map.next_value()?,
+                Field::ErrorCode => status.error_code = map.next_value()?,
+                Field::Payload => payload = Some(map.next_value()?),
+                Field::Other => { map.next_value::<IgnoredAny>()?; },
+            }
+        }
+
+        match payload {
+            Some(payload) => Ok((status, payload)),
+            None => Err(de::Error::custom(format!("missing field `{}`", self.key))),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Success(#[serde(deserialize_with = "lenient::bool")] bool);
+
+enum Field {
+    Success,
+    Message,
+    ErrorCode,
+    Payload,
+    Other,
+}
+
+struct FieldSeed<'a>(&'a str);
+
+impl<'de, 'a> DeserializeSeed<'de> for FieldSeed<'a> {
+    type Value = Field;
+
+    fn deserialize<D: Deserializer<'de>>(self, d: D) -> Result<Field, D::Error> {
+        d.deserialize_identifier(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for FieldSeed<'a> {
+    type Value = Field;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a field name")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Field, E> {
+        Ok(match v {
+            "success" => Field::Success,
+            "message" => Field::Message,
+            "error_code" => Field::ErrorCode,
+            _ if v == self.0 => Field::Payload,
+            _ => Field::Other,
+        })
+    }
+}
+
+// Hands the body to the payload, reading the `Status` fields on the way
+// through. Those few values are buffered so both can read them; everything
+// else goes straight to the payload.
+struct WholeBody<'s, D> {
+    inner: D,
+    status: &'s mut Status,
+}
+
+impl<'de, 's, D: Deserializer<'de>> Deserializer<'de> for WholeBody<'s, D> {
+    type Error = D::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, D::Error> {
+        self.inner.deserialize_map(WholeBodyVisitor { visitor, status: self.status })
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct WholeBodyVisitor<'s, V> {
+    visitor: V,
+    status: &'s mut Status,
+}
+
+impl<'de, 's, V: Visitor<'de>> Visitor<'de> for WholeBodyVisitor<'s, V> {
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an Authy response")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<V::Value, A::Error> {
+        self.visitor.visit_map(StatusTee { inner: map, status: self.status, field: None })
+    }
+}
+
+struct StatusTee<'s, A> {
+    inner: A,
+    status: &'s mut Status,
+    field: Option<Field>,
+}
+
+impl<'de, 's, A: MapAccess<'de>> MapAccess<'de> for StatusTee<'s, A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, A::Error> {
+        let key = match self.inner.next_key_seed(KeySeed)? {
+            Some(key) => key,
+            None => return Ok(None),
+        };
+
+        let field = match key.as_str() {
+            "success" => Field::Success,
+            "message" => Field::Message,
+            "error_code" => Field::ErrorCode,
+            _ => Field::Other,
+        };
+        self.field = Some(field);
+
+        match key {
+            Key::Borrowed(k) => seed.deserialize(BorrowedStrDeserializer::new(k)).map(Some),
+            Key::Owned(k) => seed.deserialize(StringDeserializer::new(k)).map(Some),
+        }
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<S::Value, A::Error> {
+        let field = self.field.take();
+        if let Some(Field::Other) | None = field {
+            return self.inner.next_value_seed(seed);
+        }
+
+        let value: Value = self.inner.next_value()?;
+        match field {
+            Some(Field::Success) => self.status.success = lenient::bool(&value).map_err(de::Error::custom)?,
+            Some(Field::Message) => self.status.message = String::deserialize(&value).map_err(de::Error::custom)?,
+            Some(Field::ErrorCode) => self.status.error_code = Option::deserialize(&value).map_err(de::Error::custom)?,
+            _ => (),
+        }
+        seed.deserialize(value).map_err(de::Error::custom)
+    }
+}
+
+// A field name, borrowed from the body unless it had to be unescaped.
+enum Key<'de> {
+    Borrowed(&'de str),
+    Owned(String),
+}
+
+impl<'de> Key<'de> {
+    fn as_str(&self) -> &str {
+        match *self {
+            Key::Borrowed(k) => k,
+            Key::Owned(ref k) => k,
+        }
+    }
+}
+
+struct KeySeed;
+
+impl<'de> DeserializeSeed<'de> for KeySeed {
+    type Value = Key<'de>;
+
+    fn deserialize<D: Deserializer<'de>>(self, d: D) -> Result<Key<'de>, D::Error> {
+        d.deserialize_str(self)
+    }
+}
+
+impl<'de> Visitor<'de> for KeySeed {
+    type Value = Key<'de>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a field name")
+    }
+
+    fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Key<'de>, E> {
+        Ok(Key::Borrowed(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Key<'de>, E> {
+        Ok(Key::Owned(v.into()))
+    }
+}
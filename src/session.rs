@@ -0,0 +1,263 @@
+//! Tracks a phone verification across requests.
+//!
+//! Please see [phone](../phone/index.html) for the calls it is built on.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use error::AuthyError;
+use client::Client;
+use phone::{Phone, PhoneCheckOutcome, PhoneStart, PhoneStartOptions, ContactType};
+
+/// Limits enforced by a `VerificationSession`.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SessionConfig {
+    /// How long to wait after sending a code before another can be sent.
+    pub resend_cooldown: Duration,
+
+    /// The most codes that can be sent.
+    pub max_sends: u32,
+
+    /// The most codes that can be checked.
+    pub max_attempts: u32,
+
+    /// Send codes with a voice call once this many have been sent over SMS
+    /// without the phone being verified. `None` never switches.
+    pub call_after_sms: Option<u32>,
+}
+
+impl Default for SessionConfig {
+    fn default() -> SessionConfig {
+        SessionConfig {
+            resend_cooldown: Duration::from_secs(30),
+            max_sends: 5,
+            max_attempts: 5,
+            call_after_sms: Some(2),
+        }
+    }
+}
+
+/// A phone verification in progress.
+///
+/// Records when codes were sent and checked so that limits hold across
+/// requests. It serializes to json, so it can be kept between sending the
+/// code and the user entering it.
+///
+/// The limits are only as good as the stored session: restoring an older
+/// copy resets the counts and the cooldown. Keep it server-side, such as in
+/// your database or a server-side web session, never in a cookie or anywhere
+/// else the user can hand back. The config isn't serialized, so that it
+/// comes from your code rather than the stored copy; give it again with
+/// `config` when restoring the session:
+///
+/// ```rust,ignore
+/// let mut session = VerificationSession::new(phone, SessionConfig::default());
+/// session.send(&c, &PhoneStartOptions::default())?;
+/// save_server_side(&user_id, &serde_json::to_string(&session)?);
+///
+/// // Later, when the user enters the code:
+/// let session: VerificationSession = serde_json::from_str(&load_server_side(&user_id))?;
+/// let mut session = session.config(SessionConfig::default());
+/// let outcome = session.check(&c, code)?;
+/// save_server_side(&user_id, &serde_json::to_string(&session)?);
+/// match outcome {
+///     PhoneCheckOutcome::Verified => (),
+///     other => (),
+/// }
+/// ```
+///
+/// Times are recorded as milliseconds since the unix epoch. The methods
+/// ending in `_at` take the current time, and are otherwise the same as those
+/// without.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct VerificationSession {
+    pub phone: Phone,
+
+    /// Left out when serialized, and the default when deserialized.
+    #[serde(skip)]
+    pub config: SessionConfig,
+
+    /// The `uuid` of the latest verification started.
+    pub uuid: Option<String>,
+
+    sms_sent: u32,
+    calls_made: u32,
+    attempts: u32,
+    verified: bool,
+    last_sent_at: Option<u64>,
+    expires_at: Option<u64>,
+}
+
+impl VerificationSession {
+    pub fn new(phone: Phone, config: SessionConfig) -> VerificationSession {
+        VerificationSession {
+            phone,
+            config,
+            uuid: None,
+            sms_sent: 0,
+            calls_made: 0,
+            attempts: 0,
+            verified: false,
+            last_sent_at: None,
+            expires_at: None,
+        }
+    }
+
+    /// Use the limits in `config`, such as after deserializing the session.
+    pub fn config(mut self, config: SessionConfig) -> VerificationSession {
+        self.config = config;
+        self
+    }
+
+    /// Send a code over the channel given by `next_channel`.
+    ///
+    /// Returns `AuthyError::ResendCooldown` with the time left when a code was
+    /// sent too recently, and `AuthyError::AttemptsExceeded` once
+    /// `max_sends` codes have been sent.
+    ///
+    /// Please see [Phone::start](../phone/struct.Phone.html#method.start)
+    pub fn send(&mut self, c: &Client, options: &PhoneStartOptions) -> Result<PhoneStart, AuthyError> {
+        let via = self.ready_to_send_at(SystemTime::now())?;
+        let phone_start = self.phone.start(c, via, options)?;
+        self.sent_at(via, &phone_start, SystemTime::now());
+
+        Ok(phone_start)
+    }
+
+    /// Check the code the user entered.
+    ///
+    /// Returns `AuthyError::AttemptsExceeded` once `max_attempts` codes have
+    /// been checked. A code that has expired according to the session is
+    /// reported as `PhoneCheckOutcome::Expired` without calling Authy.
+    ///
+    /// Please see [Phone::check](../phone/struct.Phone.html#method.check)
+    pub fn check(&mut self, c: &Client, code: &str) -> Result<PhoneCheckOutcome, AuthyError> {
+        if let Some(outcome) = self.ready_to_check_at(SystemTime::now())? {
+            return Ok(outcome);
+        }
+
+        let outcome = self.phone.check(c, code)?;
+        self.checked(outcome);
+
+        Ok(outcome)
+    }
+
+    /// The channel the next code will be sent over.
+    pub fn next_channel(&self) -> ContactType {
+        match self.config.call_after_sms {
+            Some(n) if self.sms_sent >= n => ContactType::Call,
+            _ => ContactType::SMS,
+        }
+    }
+
+    /// Checks that a code can be sent, returning the channel to send it over.
+    pub fn ready_to_send_at(&self, now: SystemTime) -> Result<ContactType, AuthyError> {
+        if self.verified {
+            return Err(AuthyError::InvalidRequest("Phone is already verified".into()));
+        }
+        if self.sends() >= self.config.max_sends {
+            return Err(AuthyError::AttemptsExceeded(format!("No more than {} codes can be sent", self.config.max_sends)));
+        }
+
+        let wait = self.time_until_resend_at(now);
+        if wait > Duration::from_secs(0) {
+            return Err(AuthyError::ResendCooldown(wait));
+        }
+
+        Ok(self.next_channel())
+    }
+
+    /// Checks that a code can be checked. Returns the outcome when it is
+    /// already known without calling Authy.
+    pub fn ready_to_check_at(&self, now: SystemTime) -> Result<Option<PhoneCheckOutcome>, AuthyError> {
+        if self.verified {
+            return Ok(Some(PhoneCheckOutcome::Verified));
+        }
+        if self.last_sent_at.is_none() {
+            return Ok(Some(PhoneCheckOutcome::NoPendingVerification));
+        }
+        if self.attempts >= self.config.max_attempts {
+            return Err(AuthyError::AttemptsExceeded(format!("No more than {} codes can be checked", self.config.max_attempts)));
+        }
+        if self.is_expired_at(now) {
+            return Ok(Some(PhoneCheckOutcome::Expired));
+        }
+
+        Ok(None)
+    }
+
+    /// Record a code sent outside of `send`.
+    pub fn sent_at(&mut self, via: ContactType, phone_start: &PhoneStart, now: SystemTime) {
+        let now = millis(now);
+        match via {
+            ContactType::SMS => self.sms_sent += 1,
+            ContactType::Call => self.calls_made += 1,
+        }
+        self.last_sent_at = Some(now);
+        self.expires_at = Some(now + u64::from(phone_start.seconds_to_expire) * 1000);
+        if phone_start.uuid.is_some() {
+            self.uuid = phone_start.uuid.clone();
+        }
+    }
+
+    /// Record a code checked outside of `check`.
+    pub fn checked(&mut self, outcome: PhoneCheckOutcome) {
+        self.attempts += 1;
+        match outcome {
+            PhoneCheckOutcome::Verified => self.verified = true,
+            PhoneCheckOutcome::Expired | PhoneCheckOutcome::NoPendingVerification => self.expires_at = Some(0),
+            PhoneCheckOutcome::WrongCode => (),
+        }
+    }
+
+    /// How long until another code can be sent, zero when one can be now.
+    pub fn time_until_resend_at(&self, now: SystemTime) -> Duration {
+        match self.last_sent_at {
+            Some(sent) => (UNIX_EPOCH + Duration::from_millis(sent) + self.config.resend_cooldown)
+                .duration_since(now)
+                .unwrap_or_default(),
+            None => Duration::from_secs(0),
+        }
+    }
+
+    pub fn time_until_resend(&self) -> Duration {
+        self.time_until_resend_at(SystemTime::now())
+    }
+
+    /// How long the latest code is valid for, or `None` when no code is
+    /// pending.
+    pub fn remaining_at(&self, now: SystemTime) -> Option<Duration> {
+        if self.verified {
+            return None;
+        }
+        self.expires_at
+            .and_then(|expires| (UNIX_EPOCH + Duration::from_millis(expires)).duration_since(now).ok())
+            .filter(|d| *d > Duration::from_secs(0))
+    }
+
+    pub fn remaining(&self) -> Option<Duration> {
+        self.remaining_at(SystemTime::now())
+    }
+
+    /// true when a code was sent but is no longer valid.
+    pub fn is_expired_at(&self, now: SystemTime) -> bool {
+        !self.verified && self.last_sent_at.is_some() && self.remaining_at(now).is_none()
+    }
+
+    pub fn is_verified(&self) -> bool {
+        self.verified
+    }
+
+    /// The number of codes sent so far.
+    pub fn sends(&self) -> u32 {
+        self.sms_sent + self.calls_made
+    }
+
+    /// The number of codes that can still be checked.
+    pub fn attempts_left(&self) -> u32 {
+        self.config.max_attempts.saturating_sub(self.attempts)
+    }
+}
+
+fn millis(t: SystemTime) -> u64 {
+    t.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
@@ -0,0 +1,216 @@
+//! Shared state for the guards built on top of the API, such as rate limits
+//! and cooldowns.
+//!
+//! Implement `Store` to keep the state somewhere shared by all of your app
+//! servers, such as Redis. `MemoryStore` and `FileStore` cover a single
+//! process and a single machine.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json;
+
+use error::AuthyError;
+
+/// A key value store with expiring keys.
+///
+/// Every method must be atomic with respect to the others, as the same keys
+/// are used from many threads and, for shared stores, many servers.
+pub trait Store: Send + Sync {
+    /// The value for `key`, unless it is missing or has expired.
+    fn get(&self, key: &str) -> Result<Option<String>, AuthyError>;
+
+    /// Set `key` to `value`, expiring after `ttl` when given.
+    fn set(&self, key: &str, value: &str, ttl: Option<Duration>) -> Result<(), AuthyError>;
+
+    /// Set `key` to `value` only when it is missing or has expired. Returns
+    /// true when the value was set.
+    fn add(&self, key: &str, value: &str, ttl: Option<Duration>) -> Result<bool, AuthyError>;
+
+    /// Add one to the counter at `key`, returning the new count. A missing
+    /// or expired counter starts from zero and expires after `ttl`; the
+    /// expiry of an existing counter is left as it is.
+    fn increment(&self, key: &str, ttl: Option<Duration>) -> Result<u64, AuthyError>;
+
+    /// Remove `key`.
+    fn remove(&self, key: &str) -> Result<(), AuthyError>;
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+struct Entry {
+    value: String,
+
+    /// Seconds since the unix epoch.
+    expires_at: Option<u64>,
+}
+
+impl Entry {
+    fn new(value: String, ttl: Option<Duration>) -> Entry {
+        Entry { value, expires_at: ttl.map(|ttl| now() + ttl.as_secs().max(1)) }
+    }
+
+    fn is_live(&self) -> bool {
+        self.expires_at.map_or(true, |e| e > now())
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+// The operations shared by the provided stores, on a map of entries.
+fn get(entries: &HashMap<String, Entry>, key: &str) -> Option<String> {
+    entries.get(key).filter(|e| e.is_live()).map(|e| e.value.clone())
+}
+
+fn add(entries: &mut HashMap<String, Entry>, key: &str, value: &str, ttl: Option<Duration>) -> bool {
+    if get(entries, key).is_some() {
+        return false;
+    }
+    entries.insert(key.into(), Entry::new(value.into(), ttl));
+    true
+}
+
+fn increment(entries: &mut HashMap<String, Entry>, key: &str, ttl: Option<Duration>) -> Result<u64, AuthyError> {
+    let count = match get(entries, key) {
+        Some(v) => v.parse::<u64>().map_err(|_| AuthyError::InvalidRequest(format!("Not a counter: {}", key)))? + 1,
+        None => {
+            entries.insert(key.into(), Entry::new("1".into(), ttl));
+            return Ok(1);
+        },
+    };
+
+    if let Some(entry) = entries.get_mut(key) {
+        entry.value = count.to_string();
+    }
+    Ok(count)
+}
+
+/// Keeps state in memory, for a single process.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> MemoryStore {
+        MemoryStore::default()
+    }
+
+    fn with<T, F: FnOnce(&mut HashMap<String, Entry>) -> T>(&self, f: F) -> T {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|_, e| e.is_live());
+        f(&mut entries)
+    }
+}
+
+impl Store for MemoryStore {
+    fn get(&self, key: &str) -> Result<Option<String>, AuthyError> {
+        Ok(self.with(|entries| get(entries, key)))
+    }
+
+    fn set(&self, key: &str, value: &str, ttl: Option<Duration>) -> Result<(), AuthyError> {
+        self.with(|entries| entries.insert(key.into(), Entry::new(value.into(), ttl)));
+        Ok(())
+    }
+
+    fn add(&self, key: &str, value: &str, ttl: Option<Duration>) -> Result<bool, AuthyError> {
+        Ok(self.with(|entries| add(entries, key, value, ttl)))
+    }
+
+    fn increment(&self, key: &str, ttl: Option<Duration>) -> Result<u64, AuthyError> {
+        self.with(|entries| increment(entries, key, ttl))
+    }
+
+    fn remove(&self, key: &str) -> Result<(), AuthyError> {
+        self.with(|entries| entries.remove(key));
+        Ok(())
+    }
+}
+
+/// Keeps state in a json file, so that it survives restarts.
+///
+/// The file is read and rewritten on every change. Access is serialized
+/// within the process, but not between processes, so each process should use
+/// its own file.
+#[derive(Debug)]
+pub struct FileStore {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileStore {
+    /// Use the file at `path`, which is created on the first change.
+    pub fn new<P: Into<PathBuf>>(path: P) -> FileStore {
+        FileStore { path: path.into(), lock: Mutex::new(()) }
+    }
+
+    fn read(&self) -> Result<HashMap<String, Entry>, AuthyError> {
+        match fs::read(&self.path) {
+            Ok(ref data) if data.is_empty() => Ok(HashMap::new()),
+            Ok(data) => Ok(serde_json::from_slice(&data)?),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    // Writes to a temporary file first, so a crash can't leave the file half
+    // written.
+    fn write(&self, entries: &HashMap<String, Entry>) -> Result<(), AuthyError> {
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(&serde_json::to_vec(entries)?)?;
+        file.sync_all()?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    fn with<T, F>(&self, f: F) -> Result<T, AuthyError>
+        where F: FnOnce(&mut HashMap<String, Entry>) -> Result<T, AuthyError>,
+    {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut entries = self.read()?;
+        entries.retain(|_, e| e.is_live());
+
+        let before = entries.clone();
+        let result = f(&mut entries)?;
+        if entries != before {
+            self.write(&entries)?;
+        }
+        Ok(result)
+    }
+}
+
+impl Store for FileStore {
+    fn get(&self, key: &str) -> Result<Option<String>, AuthyError> {
+        self.with(|entries| Ok(get(entries, key)))
+    }
+
+    fn set(&self, key: &str, value: &str, ttl: Option<Duration>) -> Result<(), AuthyError> {
+        self.with(|entries| {
+            entries.insert(key.into(), Entry::new(value.into(), ttl));
+            Ok(())
+        })
+    }
+
+    fn add(&self, key: &str, value: &str, ttl: Option<Duration>) -> Result<bool, AuthyError> {
+        self.with(|entries| Ok(add(entries, key, value, ttl)))
+    }
+
+    fn increment(&self, key: &str, ttl: Option<Duration>) -> Result<u64, AuthyError> {
+        self.with(|entries| increment(entries, key, ttl))
+    }
+
+    fn remove(&self, key: &str) -> Result<(), AuthyError> {
+        self.with(|entries| {
+            entries.remove(key);
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,233 @@
+//! Generating and verifying time-based one-time passwords (RFC 6238)
+//! locally, without calling Authy.
+//!
+//! Codes are compatible with the Authy app and other authenticator apps, and
+//! with hardware tokens. A `Totp` reports its result as a `VerifyOutcome`, so
+//! it can stand in for `User::verify`:
+//!
+//! ```rust
+//! use authy::{Totp, VerifyOutcome};
+//!
+//! let totp = Totp::from_base32("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ").unwrap();
+//! let code = totp.generate_at(1111111109).unwrap();
+//!
+//! assert_eq!(code, "081804");
+//! assert_eq!(totp.verify_at(&code, 1111111109 + 30), Ok(VerifyOutcome::Valid));
+//! assert_eq!(totp.verify_at(&code, 1111111109 + 90), Ok(VerifyOutcome::Invalid));
+//! ```
+
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+
+use error::AuthyError;
+use compare::constant_time_eq;
+use user::VerifyOutcome;
+
+/// The fewest digits a code can have.
+pub const MIN_DIGITS: u32 = 6;
+
+/// The most digits a code can have.
+pub const MAX_DIGITS: u32 = 10;
+
+/// The most periods either side of the current one codes can be accepted
+/// from. Each one costs every verify two more hashes.
+pub const MAX_SKEW: u32 = 10;
+
+/// The hash function codes are generated with.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Algorithm {
+    #[default]
+    SHA1,
+    SHA256,
+    SHA512,
+}
+
+/// A shared secret and the parameters codes are generated with.
+///
+/// The defaults, 6 digits every 30 seconds using SHA1, are those used by
+/// Authy and most authenticator apps. The secret is left out of its `Debug`
+/// output, so it doesn't end up in logs.
+#[derive(Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Totp {
+    pub secret: Vec<u8>,
+    pub algorithm: Algorithm,
+
+    /// The length of the codes.
+    pub digits: u32,
+
+    /// How many seconds each code is valid for.
+    pub period: u64,
+
+    /// How many periods either side of the current one codes are accepted
+    /// from, to allow for clock drift and slow typing.
+    pub skew: u32,
+}
+
+impl Totp {
+    pub fn new(secret: &[u8]) -> Totp {
+        Totp {
+            secret: secret.to_vec(),
+            algorithm: Algorithm::SHA1,
+            digits: 6,
+            period: 30,
+            skew: 1,
+        }
+    }
+
+    /// Use a secret in the base32 encoding shown to users and in QR codes,
+    /// such as the `secret` of `api::user::QrCode`. Case, spaces and padding
+    /// are ignored.
+    pub fn from_base32(secret: &str) -> Result<Totp, AuthyError> {
+        let secret = base32_decode(secret)
+            .ok_or_else(|| AuthyError::InvalidRequest("Secret is not valid base32".into()))?;
+        Ok(Totp::new(&secret))
+    }
+
+    pub fn algorithm(mut self, algorithm: Algorithm) -> Totp {
+        self.algorithm = algorithm;
+        self
+    }
+
+    pub fn digits(mut self, digits: u32) -> Totp {
+        self.digits = digits;
+        self
+    }
+
+    pub fn period(mut self, period: u64) -> Totp {
+        self.period = period;
+        self
+    }
+
+    pub fn skew(mut self, skew: u32) -> Totp {
+        self.skew = skew;
+        self
+    }
+
+    /// Check the parameters, returning `AuthyError::InvalidRequest` for ones
+    /// that can't generate codes.
+    pub fn validate(&self) -> Result<(), AuthyError> {
+        if self.secret.is_empty() {
+            return Err(AuthyError::InvalidRequest("Secret must not be empty".into()));
+        }
+        if !(MIN_DIGITS..=MAX_DIGITS).contains(&self.digits) {
+            return Err(AuthyError::InvalidRequest(format!("Digits must be between {} and {}", MIN_DIGITS, MAX_DIGITS)));
+        }
+        if self.period == 0 {
+            return Err(AuthyError::InvalidRequest("Period must be at least 1 second".into()));
+        }
+        if self.skew > MAX_SKEW {
+            return Err(AuthyError::InvalidRequest(format!("Skew must be at most {}", MAX_SKEW)));
+        }
+        Ok(())
+    }
+
+    /// The code for `time`, in seconds since the unix epoch.
+    pub fn generate_at(&self, time: u64) -> Result<String, AuthyError> {
+        self.validate()?;
+        Ok(self.code(time / self.period))
+    }
+
+    /// The code for the current time.
+    pub fn generate(&self) -> Result<String, AuthyError> {
+        self.generate_at(now())
+    }
+
+    /// Check `token` against the codes for `time`, in seconds since the unix
+    /// epoch, and the `skew` periods either side of it.
+    ///
+    /// Returns `VerifyOutcome::Valid` or `VerifyOutcome::Invalid`. Surrounding
+    /// whitespace is ignored, and codes are compared in constant time.
+    pub fn verify_at(&self, token: &str, time: u64) -> Result<VerifyOutcome, AuthyError> {
+        self.validate()?;
+
+        let token = token.trim();
+        let counter = time / self.period;
+        let first = counter.saturating_sub(u64::from(self.skew));
+        let last = counter.saturating_add(u64::from(self.skew));
+
+        // Every step is checked, so timing doesn't reveal which one matched.
+        let mut valid = false;
+        for c in first..=last {
+            valid |= constant_time_eq(self.code(c).as_bytes(), token.as_bytes());
+        }
+
+        if valid {
+            Ok(VerifyOutcome::Valid)
+        } else {
+            Ok(VerifyOutcome::Invalid)
+        }
+    }
+
+    /// Check `token` against the codes for the current time.
+    ///
+    /// Please see [Totp::verify_at](#method.verify_at)
+    pub fn verify(&self, token: &str) -> Result<VerifyOutcome, AuthyError> {
+        self.verify_at(token, now())
+    }
+
+    // HOTP (RFC 4226) for the counter.
+    fn code(&self, counter: u64) -> String {
+        let message = counter.to_be_bytes();
+        let hash = match self.algorithm {
+            Algorithm::SHA1 => hmac(Hmac::<Sha1>::new_from_slice(&self.secret), &message),
+            Algorithm::SHA256 => hmac(Hmac::<Sha256>::new_from_slice(&self.secret), &message),
+            Algorithm::SHA512 => hmac(Hmac::<Sha512>::new_from_slice(&self.secret), &message),
+        };
+
+        let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([hash[offset], hash[offset + 1], hash[offset + 2], hash[offset + 3]]) & 0x7fff_ffff;
+
+        let code = u64::from(binary) % 10u64.pow(self.digits);
+        format!("{:0width$}", code, width = self.digits as usize)
+    }
+}
+
+impl fmt::Debug for Totp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Totp")
+            .field("secret", &"<redacted>")
+            .field("algorithm", &self.algorithm)
+            .field("digits", &self.digits)
+            .field("period", &self.period)
+            .field("skew", &self.skew)
+            .finish()
+    }
+}
+
+fn hmac<M: Mac, E: fmt::Debug>(mac: Result<M, E>, message: &[u8]) -> Vec<u8> {
+    let mut mac = mac.expect("HMAC can take key of any size");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn base32_decode(s: &str) -> Option<Vec<u8>> {
+    let mut bytes = vec![];
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for c in s.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+        let value = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u32 - 'A' as u32,
+            c @ '2'..='7' => c as u32 - '2' as u32 + 26,
+            _ => return None,
+        };
+
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    Some(bytes)
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
@@ -3,11 +3,165 @@
 //! Please see [api::user](../api/user/index.html) for more details.
 
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use error::AuthyError;
 use client::{Client, Status};
+use store::Store;
+use recovery::{self, RecoveryCodes};
+use replay::ReplayCache;
 use api;
-pub use api::user::{PhoneCall, ActivityType};
+pub use api::user::{PhoneCall, ActivityType, QrCode, VerifyOptions, TokenRequestOptions};
+
+/// How a token was judged by `User::verify`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum VerifyOutcome {
+    /// The token is valid.
+    Valid,
+
+    /// The token is invalid, or was sent for a different action.
+    Invalid,
+
+    /// Authy accepted the token without checking it, because the user has
+    /// never confirmed a token. It should not be trusted as a second factor.
+    AcceptedUnconfirmed,
+
+    /// The user's account is disabled, so the token wasn't accepted.
+    UserDisabled,
+
+    /// Too many tokens have been tried; try again later.
+    RateLimited,
+
+    /// The token was already accepted, and was rejected by a `ReplayCache`.
+    Replayed,
+}
+
+impl VerifyOutcome {
+    /// true only for `VerifyOutcome::Valid`.
+    pub fn is_valid(&self) -> bool {
+        *self == VerifyOutcome::Valid
+    }
+}
+
+/// What `SendCooldown` does with a send during the cooldown.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum CooldownMode {
+    /// Return `AuthyError::ResendCooldown`.
+    Reject,
+
+    /// Skip the send and return `SendOutcome::Coalesced`, as the code sent
+    /// before is still valid.
+    Coalesce,
+}
+
+/// Returned by `User::sms_with_cooldown` and `User::call_with_cooldown`.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum SendOutcome {
+    /// A code was sent.
+    Sent(PhoneCall),
+
+    /// No code was sent, because one was sent within the cooldown. Another
+    /// can be sent after the duration.
+    Coalesced(Duration),
+}
+
+/// Limits how often codes are sent to each user, over SMS and voice
+/// combined.
+///
+/// The time of the last send is kept in a `Store`, in milliseconds since the
+/// unix epoch, so that the cooldown holds across app servers sharing it:
+///
+/// ```rust,ignore
+/// let cooldown = SendCooldown::new(store, Duration::from_secs(60)).mode(CooldownMode::Coalesce);
+///
+/// match user.sms_with_cooldown(&c, &TokenRequestOptions::default(), &cooldown)? {
+///     SendOutcome::Sent(_) => println!("Code sent"),
+///     SendOutcome::Coalesced(wait) => println!("Use the code already sent, or try again in {}s", wait.as_secs()),
+/// }
+/// ```
+#[derive(Clone)]
+pub struct SendCooldown {
+    pub window: Duration,
+    pub mode: CooldownMode,
+    store: Arc<dyn Store>,
+}
+
+impl SendCooldown {
+    /// A cooldown of `window` between sends, rejecting sends within it.
+    pub fn new(store: Arc<dyn Store>, window: Duration) -> SendCooldown {
+        SendCooldown { window, mode: CooldownMode::Reject, store }
+    }
+
+    pub fn mode(mut self, mode: CooldownMode) -> SendCooldown {
+        self.mode = mode;
+        self
+    }
+
+    /// How long until a code can be sent to user `id`, zero when one can be
+    /// now.
+    pub fn time_until_send(&self, id: u32) -> Result<Duration, AuthyError> {
+        let sent_at = match self.store.get(&key(id))? {
+            Some(v) => v.parse::<u64>().map_err(|_| AuthyError::InvalidRequest(format!("Not a send time: {}", v)))?,
+            None => return Ok(Duration::from_secs(0)),
+        };
+
+        Ok((UNIX_EPOCH + Duration::from_millis(sent_at) + self.window)
+            .duration_since(SystemTime::now())
+            .unwrap_or_default())
+    }
+
+    /// Claim the next send to user `id`, starting the cooldown.
+    ///
+    /// Returns `AuthyError::ResendCooldown` with the time left when the
+    /// cooldown has already started, whatever the mode.
+    pub fn acquire(&self, id: u32) -> Result<(), AuthyError> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+        if self.store.add(&key(id), &now.to_string(), Some(self.window))? {
+            return Ok(());
+        }
+
+        // The last send can expire between adding and reading it back.
+        let wait = self.time_until_send(id)?;
+        if wait == Duration::from_secs(0) && self.store.add(&key(id), &now.to_string(), Some(self.window))? {
+            return Ok(());
+        }
+        Err(AuthyError::ResendCooldown(wait))
+    }
+
+    /// End the cooldown for user `id`, such as when a send failed.
+    pub fn release(&self, id: u32) -> Result<(), AuthyError> {
+        self.store.remove(&key(id))
+    }
+
+    /// Call `f` to send a code to user `id`, unless the cooldown has started.
+    ///
+    /// During the cooldown, `CooldownMode::Reject` returns
+    /// `AuthyError::ResendCooldown` and `CooldownMode::Coalesce` returns
+    /// `SendOutcome::Coalesced`, without calling `f`. When `f` fails, the
+    /// cooldown is released so the send can be tried again.
+    pub fn send<F>(&self, id: u32, f: F) -> Result<SendOutcome, AuthyError>
+        where F: FnOnce() -> Result<PhoneCall, AuthyError>,
+    {
+        match self.acquire(id) {
+            Ok(()) => (),
+            Err(AuthyError::ResendCooldown(wait)) if self.mode == CooldownMode::Coalesce => return Ok(SendOutcome::Coalesced(wait)),
+            Err(e) => return Err(e),
+        }
+
+        match f() {
+            Ok(phone) => Ok(SendOutcome::Sent(phone)),
+            Err(e) => {
+                self.release(id)?;
+                Err(e)
+            },
+        }
+    }
+}
+
+fn key(id: u32) -> String {
+    format!("authy:cooldown:{}", id)
+}
 
 /// An Authy user as part of the 'high level' Authy interface.
 ///
@@ -47,7 +201,7 @@ impl User {
     /// Please see [api::user::create](../api/user/fn.create.html)
     pub fn create(c: &Client, email: &str, country_code: u16, phone: &str, send_instructions: bool) -> Result<User, AuthyError> {
         let (status, user_new) = api::user::create(c, email, country_code, phone, send_instructions)?;
-        assert!(status.success);
+        status.check()?;
 
         Self::find(c, user_new.id)
     }
@@ -67,8 +221,8 @@ impl User {
     /// Please see [api::user::status](../api/user/fn.status.html)
     pub fn update(&mut self, c: &Client) -> Result<(), AuthyError> {
         let (status, u) = api::user::status(c, self.id)?;
-        assert!(status.success);
-         
+        status.check()?;
+
         self.id = u.id;
         self.confirmed = u.confirmed;
         self.registered = u.registered;
@@ -86,52 +240,136 @@ impl User {
     /// Please see [api::user::delete](../api/user/fn.delete.html)
     pub fn delete(&mut self, c: &Client) -> Result<(), AuthyError> {
         let status = api::user::delete(c, self.id)?;
-        assert!(status.success);
+        status.check()?;
         self.update(c)?;
         Ok(())
     }
 
-    /// Perform a verification request. Returns Ok(true) for a successful
-    /// verification and Ok(false) when the verification code was invalid.
+    /// Perform a verification request, returning how the token was judged.
+    ///
+    /// A token Authy accepts only because the user has never confirmed one is
+    /// reported as `VerifyOutcome::AcceptedUnconfirmed` rather than `Valid`,
+    /// going by `confirmed` as of the last update. Set `force` in `options`
+    /// to have Authy check the token for unconfirmed users too. Tokens aren't
+    /// checked at all for users known to be disabled.
+    ///
+    /// The user's status is updated once a token is accepted, so a user
+    /// disabled since the last update is reported as
+    /// `VerifyOutcome::UserDisabled`.
     ///
     /// Please see [api::user::verify](../api/user/fn.verify.html)
-    pub fn verify(&mut self, c: &Client, token: &str) -> Result<bool, AuthyError> {
-        match api::user::verify(c, self.id, token) {
+    pub fn verify(&mut self, c: &Client, token: &str, options: &VerifyOptions) -> Result<VerifyOutcome, AuthyError> {
+        if self.account_disabled {
+            return Ok(VerifyOutcome::UserDisabled);
+        }
+        let confirmed = self.confirmed;
+
+        match api::user::verify(c, self.id, token, options) {
             Ok(status) => {
-                assert!(status.success);
+                status.check()?;
                 self.update(c)?;
-                Ok(true)
+                if self.account_disabled {
+                    return Ok(VerifyOutcome::UserDisabled);
+                }
+                if !(confirmed || options.force) {
+                    return Ok(VerifyOutcome::AcceptedUnconfirmed);
+                }
+
+                Ok(VerifyOutcome::Valid)
             }
-            Err(AuthyError::UnauthorizedKey(Status { ref message,.. }))
-                if message == "Token is invalid" => Ok(false),
+            Err(AuthyError::UnauthorizedKey(Status { ref message, ref error_code, .. }))
+                if message == "Token is invalid" || error_code.as_ref().is_some_and(|c| c == "60020") => Ok(VerifyOutcome::Invalid),
+            Err(AuthyError::TooManyRequests(_)) => Ok(VerifyOutcome::RateLimited),
             Err(e) => Err(e)
         }
     }
 
+    /// Like `verify`, but reports tokens that were already accepted as
+    /// `VerifyOutcome::Replayed` without sending them to Authy.
+    ///
+    /// Please see [ReplayCache::verify](../replay/struct.ReplayCache.html#method.verify)
+    pub fn verify_with_replay_cache(&mut self, c: &Client, token: &str, options: &VerifyOptions, cache: &ReplayCache) -> Result<VerifyOutcome, AuthyError> {
+        cache.verify(self.id, token, || self.verify(c, token, options))
+    }
+
+    /// Verify either a token or one of the user's recovery codes, such as
+    /// from a single login field.
+    ///
+    /// Recovery codes are checked locally and used up, without calling Authy,
+    /// so they still work when Authy is unreachable. Anything else is passed
+    /// to `verify`. A disabled user gets `UserDisabled` either way, and
+    /// their recovery code is left unused.
+    ///
+    /// Please see [recovery](../recovery/index.html)
+    pub fn verify_or_recover(&mut self, c: &Client, token: &str, options: &VerifyOptions, recovery: &RecoveryCodes) -> Result<VerifyOutcome, AuthyError> {
+        if self.account_disabled {
+            return Ok(VerifyOutcome::UserDisabled);
+        }
+
+        if recovery::is_recovery_code(token) {
+            return recovery.verify(self.id, token);
+        }
+
+        self.verify(c, token, options)
+    }
+
     /// Requests that the Authy service send the user a verification code over
     /// SMS. This request will be ignored if the user is using the Authy
-    /// Mobile app unless force is set to true.
+    /// Mobile app unless `force` is set in `options`.
     ///
     /// Please see [api::user::sms](../api/user/fn.sms.html)
-    pub fn sms(&self, c: &Client, force: bool, action: Option<&str>, action_message: Option<&str>) -> Result<PhoneCall, AuthyError> {
-        let (status, phone) = api::user::sms(c, self.id, force, action, action_message)?;
-        assert!(status.success);
+    pub fn sms(&self, c: &Client, options: &TokenRequestOptions) -> Result<PhoneCall, AuthyError> {
+        let (status, phone) = api::user::sms(c, self.id, options)?;
+        status.check()?;
 
         Ok(phone)
     }
 
     /// Requests that the Authy service send the user a verification code over 
     /// the phone. This request will be ignored if the user is using the Authy 
-    /// Mobile app unless force is set to true.
+    /// Mobile app unless `force` is set in `options`.
     ///
     /// Please see [api::user::call](../api/user/fn.call.html)
-    pub fn call(&self, c: &Client, force: bool, action: Option<&str>, action_message: Option<&str>) -> Result<PhoneCall, AuthyError> {
-        let (status, phone) = api::user::call(c, self.id, force, action, action_message)?;
-        assert!(status.success);
+    pub fn call(&self, c: &Client, options: &TokenRequestOptions) -> Result<PhoneCall, AuthyError> {
+        let (status, phone) = api::user::call(c, self.id, options)?;
+        status.check()?;
 
         Ok(phone)
     }
 
+    /// Like `sms`, but only once per `cooldown`.
+    ///
+    /// A send that fails doesn't start the cooldown.
+    pub fn sms_with_cooldown(&self, c: &Client, options: &TokenRequestOptions, cooldown: &SendCooldown) -> Result<SendOutcome, AuthyError> {
+        options.validate()?;
+        cooldown.send(self.id, || self.sms(c, options))
+    }
+
+    /// Like `call`, but only once per `cooldown`.
+    ///
+    /// A send that fails doesn't start the cooldown.
+    pub fn call_with_cooldown(&self, c: &Client, options: &TokenRequestOptions, cooldown: &SendCooldown) -> Result<SendOutcome, AuthyError> {
+        options.validate()?;
+        cooldown.send(self.id, || self.call(c, options))
+    }
+
+    /// How long until a code can be sent to the user, zero when one can be
+    /// now.
+    pub fn time_until_send(&self, cooldown: &SendCooldown) -> Result<Duration, AuthyError> {
+        cooldown.time_until_send(self.id)
+    }
+
+    /// Generates a QR code the user can scan to add their account to any TOTP
+    /// authenticator app.
+    ///
+    /// Please see [api::user::generate_qr](../api/user/fn.generate_qr.html)
+    pub fn qr_code(&self, c: &Client, label: Option<&str>, size: Option<u16>) -> Result<QrCode, AuthyError> {
+        let (status, qr_code) = api::user::generate_qr(c, self.id, label, size)?;
+        status.check()?;
+
+        Ok(qr_code)
+    }
+
     /// Optionally, you can register some of the activities that your user do
     /// on your application. This helps us to identify fraudulent behaviours.
     /// For example if you register that a user reset his password and then he
@@ -141,7 +379,7 @@ impl User {
     /// Please see [api::user::register_activity](../api/user/fn.register_activity.html)
     pub fn register_activity(&self, c: &Client, data: Option<&HashMap<&str, String>>, activity_type: ActivityType, user_ip: &str) -> Result<(), AuthyError> {
         let status = api::user::register_activity(c, self.id, data, activity_type, user_ip)?;
-        assert!(status.success);
+        status.check()?;
         Ok(())
     }
 }
@@ -5,7 +5,7 @@ mod api_app {
     const API_URL: &'static str = "https://sandbox-api.authy.com";
     const API_KEY: &'static str = "bf12974d70818a08199d17d5e2bae630";
 
-    use super::authy::{Client, Status, AuthyError};
+    use super::authy::{Client, Status, AuthyError, Endpoint, Method};
     use super::authy::api::app;
 
     #[test]
@@ -44,4 +44,25 @@ mod api_app {
         let (status, stats) = app::stats(&c).expect("Stats of authy app");
         assert!(status.success);
     }
+
+    struct AppDetails;
+
+    impl Endpoint for AppDetails {
+        type Response = app::Details;
+
+        fn method(&self) -> Method { Method::GET }
+        fn prefix(&self) -> &str { "protected" }
+        fn path(&self) -> String { "app/details".into() }
+        fn key(&self) -> Option<&str> { Some("app") }
+    }
+
+    #[test]
+    fn custom_endpoint() {
+        let mut c = Client::new(API_URL, API_KEY);
+        c.retry_wait = 3000;
+        c.retry_count = 10;
+        let (status, details) = c.execute(&AppDetails).expect("Details of authy app");
+        assert!(status.success);
+        assert_eq!(details.name, "Sandbox App 1");
+    }
 }
@@ -5,9 +5,11 @@ mod onetouch {
     const API_URL: &'static str = "https://sandbox-api.authy.com";
     const API_KEY: &'static str = "bf12974d70818a08199d17d5e2bae630";
 
-    use super::authy::Client;
+    use std::time::Duration;
+
+    use super::authy::{Client, AuthyError};
     use super::authy::api::user;
-    use super::authy::api::onetouch;
+    use super::authy::api::onetouch::{self, ApprovalRequestBuilder, ApprovalStatus, LogoResolution};
 
     #[test]
     #[ignore]
@@ -19,9 +21,90 @@ mod onetouch {
         let (status, user) = user::create(&c, "user@domain.com", 54, "317-338-9302", false).expect("User to be created");
         assert!(status.success);
 
-        let (status, request) = onetouch::request(&c, user.id, "Hello!", None, None, None, None).expect("Request");
+        let request = ApprovalRequestBuilder::new("Hello!")
+            .detail("Username", "Bill Smith")
+            .hidden_detail("ip_address", "10.10.3.203")
+            .logo(LogoResolution::Default, "https://example.com/logo.png")
+            .expires_in(Duration::from_secs(120));
+        let (status, request) = onetouch::create(&c, user.id, &request).expect("Request");
         assert!(status.success);
 
         println!("{:#?}", request);
+
+        let (status, request) = onetouch::status(&c, &request.uuid).expect("Status");
+        assert!(status.success);
+        assert_eq!(request.status, Some(ApprovalStatus::Pending));
+    }
+
+    #[test]
+    fn validate() {
+        let request = ApprovalRequestBuilder::new("Login requested")
+            .detail("Username", "Bill Smith")
+            .detail("Location", "California, USA")
+            .logo(LogoResolution::Default, "https://example.com/logo.png")
+            .logo(LogoResolution::High, "https://example.com/logo@3x.png")
+            .reason("Login");
+
+        assert_eq!(request.validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_message() {
+        match ApprovalRequestBuilder::new(" ").validate() {
+            Err(AuthyError::InvalidRequest(_)) => (),
+            o => unreachable!("Expecting AuthyError::InvalidRequest, got: {:?}", o),
+        };
+
+        let message = "x".repeat(onetouch::MAX_MESSAGE_LENGTH + 1);
+        match ApprovalRequestBuilder::new(&message).validate() {
+            Err(AuthyError::InvalidRequest(_)) => (),
+            o => unreachable!("Expecting AuthyError::InvalidRequest, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn validate_duplicate_detail() {
+        let request = ApprovalRequestBuilder::new("Login requested")
+            .detail("Username", "Bill Smith")
+            .hidden_detail("Username", "bill")
+            .detail("Username", "Bill Smith");
+
+        match request.validate() {
+            Err(AuthyError::InvalidRequest(message)) => assert_eq!(message, "Duplicate key in details: Username"),
+            o => unreachable!("Expecting AuthyError::InvalidRequest, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn validate_expires_in() {
+        let request = ApprovalRequestBuilder::new("Login requested").expires_in(Duration::from_millis(500));
+        match request.validate() {
+            Err(AuthyError::InvalidRequest(message)) => assert_eq!(message, "Approval request must expire in at least 1 second"),
+            o => unreachable!("Expecting AuthyError::InvalidRequest, got: {:?}", o),
+        };
+
+        for d in &[Duration::from_secs(0), Duration::from_secs(1), Duration::from_millis(1500)] {
+            assert_eq!(ApprovalRequestBuilder::new("Login requested").expires_in(*d).validate(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn validate_logos() {
+        let request = ApprovalRequestBuilder::new("Login requested")
+            .logo(LogoResolution::Low, "https://example.com/a.png")
+            .logo(LogoResolution::Low, "https://example.com/b.png");
+
+        match request.validate() {
+            Err(AuthyError::InvalidRequest(message)) => assert_eq!(message, "More than one low logo"),
+            o => unreachable!("Expecting AuthyError::InvalidRequest, got: {:?}", o),
+        };
+
+        let request = ApprovalRequestBuilder::new("Login requested")
+            .logo(LogoResolution::Default, "logo.png");
+
+        match request.validate() {
+            Err(AuthyError::InvalidRequest(message)) => assert_eq!(message, "Invalid logo url: logo.png"),
+            o => unreachable!("Expecting AuthyError::InvalidRequest, got: {:?}", o),
+        };
     }
 }
@@ -5,8 +5,8 @@ mod phone {
     const API_URL: &'static str = "https://sandbox-api.authy.com";
     const API_KEY: &'static str = "bf12974d70818a08199d17d5e2bae630";
 
-    use super::authy::{Client, Status, AuthyError};
-    use super::authy::api::phone::{self, ContactType};
+    use super::authy::{Client, Status, AuthyError, Locale};
+    use super::authy::api::phone::{self, ContactType, PhoneStartOptions, Verification};
 
     #[test]
     fn info() {
@@ -27,19 +27,53 @@ mod phone {
         let mut c = Client::new(API_URL, API_KEY);
         c.retry_wait = 3000;
         c.retry_count = 10;
-        let (status, info) = phone::start(&c, ContactType::SMS, 54, "317-338-9302", None, None).expect("PhoneVerification");
+        let (status, info) = phone::start(&c, ContactType::SMS, 54, "317-338-9302", &PhoneStartOptions::default()).expect("PhoneVerification");
         assert!(status.success);
         assert_eq!(info.carrier, "Google Voice");
         assert_eq!(info.message, "Text message sent to +54 317-338-9302.");
     }
 
 
+    #[test]
+    fn start_options() {
+        let mut c = Client::new(API_URL, API_KEY);
+        c.retry_wait = 3000;
+        c.retry_count = 10;
+        let options = PhoneStartOptions::new()
+            .code_length(6)
+            .locale(Locale::PortugueseBR)
+            .app_hash("FA+9qCX9VSu");
+        let (status, _) = phone::start(&c, ContactType::SMS, 54, "317-338-9302", &options).expect("PhoneVerification");
+        assert!(status.success);
+    }
+
+    #[test]
+    fn start_options_validate() {
+        assert_eq!(PhoneStartOptions::new().code_length(4).custom_code("1234").validate(), Ok(()));
+        assert_eq!(PhoneStartOptions::new().code_length(10).validate(), Ok(()));
+
+        let invalid = vec![
+            PhoneStartOptions::new().code_length(3),
+            PhoneStartOptions::new().code_length(11),
+            PhoneStartOptions::new().custom_code("12a4"),
+            PhoneStartOptions::new().code_length(6).custom_code("1234"),
+            PhoneStartOptions::new().custom_message(" "),
+            PhoneStartOptions::new().app_hash("too short"),
+        ];
+        for options in invalid {
+            match options.validate() {
+                Err(AuthyError::InvalidRequest(_)) => (),
+                o => unreachable!("Expecting AuthyError::InvalidRequest for {:?}, got: {:?}", options, o),
+            };
+        }
+    }
+
     #[test]
     fn start_fail() {
         let mut c = Client::new(API_URL, API_KEY);
         c.retry_wait = 3000;
         c.retry_count = 10;
-        let res = phone::start(&c, ContactType::Call, 54, "555-9302", None, None);
+        let res = phone::start(&c, ContactType::Call, 54, "555-9302", &PhoneStartOptions::default());
 
         match res {
             Err(AuthyError::BadRequest(Status{success, message, ..})) => {
@@ -58,10 +92,28 @@ mod phone {
         let mut c = Client::new(API_URL, API_KEY);
         c.retry_wait = 3000;
         c.retry_count = 10;
-        let (status, _) = phone::start(&c, ContactType::Call, 54, "317-555-9302", None, None).expect("PhoneVerification");
+        let (status, _) = phone::start(&c, ContactType::Call, 54, "317-555-9302", &PhoneStartOptions::default()).expect("PhoneVerification");
         assert!(status.success);
 
         let status = phone::check(&c, 54, "317-555-9302", "0000").expect("Status");
         assert!(status.success);
     }
+
+    #[test]
+    #[ignore]
+    // As with check, the sandbox doesn't kick off a verification to look up.
+    fn status() {
+        let mut c = Client::new(API_URL, API_KEY);
+        c.retry_wait = 3000;
+        c.retry_count = 10;
+        let (status, start) = phone::start(&c, ContactType::SMS, 54, "317-338-9302", &PhoneStartOptions::default()).expect("PhoneVerification");
+        assert!(status.success);
+
+        let uuid = start.uuid.expect("uuid");
+        let (status, _) = phone::status(&c, Verification::Uuid(&uuid)).expect("PhoneVerificationStatus");
+        assert!(status.success);
+
+        let (status, _) = phone::status(&c, Verification::Number { country_code: 54, phone: "317-338-9302" }).expect("PhoneVerificationStatus");
+        assert!(status.success);
+    }
 }
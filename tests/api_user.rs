@@ -7,8 +7,17 @@ mod user {
 
     use std::collections::HashMap;
 
-    use super::authy::{Client, Status, AuthyError};
-    use super::authy::api::user::{self, ActivityType};
+    use super::authy::{Client, Status, AuthyError, Locale};
+    use super::authy::api::user::{self, ActivityType, VerifyOptions, TokenRequestOptions};
+
+    fn action_options() -> TokenRequestOptions<'static> {
+        TokenRequestOptions {
+            force: true,
+            action: Some("an_action"),
+            action_message: Some("a_message"),
+            ..TokenRequestOptions::default()
+        }
+    }
 
     #[test]
     fn new() {
@@ -86,7 +95,7 @@ mod user {
         let (status, user) = user::create(&c, "user@domain.com", 54, "317-338-9302", false).expect("User to be created");
         assert!(status.success);
 
-        let status = user::verify(&c, user.id, "0000000").expect("Valid token");
+        let status = user::verify(&c, user.id, "0000000", &VerifyOptions::default()).expect("Valid token");
         assert!(status.success);
     }
 
@@ -98,7 +107,7 @@ mod user {
         let (status, user) = user::create(&c, "user@domain.com", 54, "317-338-9302", false).expect("User to be created");
         assert!(status.success);
 
-        let res = user::verify(&c, user.id, "123456");
+        let res = user::verify(&c, user.id, "123456", &VerifyOptions::default());
 
         match res {
             Err(AuthyError::UnauthorizedKey(Status{success, message, ..})) => {
@@ -109,6 +118,37 @@ mod user {
         };
     }
 
+    #[test]
+    fn verify_action() {
+        let mut c = Client::new(API_URL, API_KEY);
+        c.retry_wait = 3000;
+        c.retry_count = 10;
+        let (status, user) = user::create(&c, "user@domain.com", 54, "317-338-9302", false).expect("User to be created");
+        assert!(status.success);
+
+        let (status, _) = user::sms(&c, user.id, &action_options()).expect("Phone");
+        assert!(status.success);
+
+        let options = VerifyOptions { force: false, action: Some("an_action") };
+        let status = user::verify(&c, user.id, "0000000", &options).expect("Valid token");
+        assert!(status.success);
+    }
+
+    #[test]
+    fn verify_force() {
+        let mut c = Client::new(API_URL, API_KEY);
+        c.retry_wait = 3000;
+        c.retry_count = 10;
+        let (status, user) = user::create(&c, "user@domain.com", 54, "317-338-9302", false).expect("User to be created");
+        assert!(status.success);
+
+        let options = VerifyOptions { force: true, ..VerifyOptions::default() };
+        match user::verify(&c, user.id, "123456", &options) {
+            Err(AuthyError::UnauthorizedKey(_)) => (),
+            o => unreachable!("Expecting AuthyError::UnauthorizedKey, got: {:?}", o),
+        };
+    }
+
     #[test]
     fn sms() {
         let mut c = Client::new(API_URL, API_KEY);
@@ -117,7 +157,7 @@ mod user {
         let (status, user) = user::create(&c, "user@domain.com", 54, "317-338-9302", false).expect("User to be created");
         assert!(status.success);
 
-        let (status, sms) = user::sms(&c, user.id, false, None, None).expect("Phone");
+        let (status, sms) = user::sms(&c, user.id, &TokenRequestOptions::default()).expect("Phone");
         assert!(status.success);
 
         assert_eq!(sms.cellphone, "+54-XXX-XXX-XX02");
@@ -131,12 +171,38 @@ mod user {
         let (status, user) = user::create(&c, "user@domain.com", 54, "317-338-9302", false).expect("User to be created");
         assert!(status.success);
 
-        let (status, sms) = user::sms(&c, user.id, true, Some("an_action"), Some("a_message")).expect("Phone");
+        let (status, sms) = user::sms(&c, user.id, &action_options()).expect("Phone");
+        assert!(status.success);
+
+        assert_eq!(sms.cellphone, "+54-XXX-XXX-XX02");
+    }
+
+    #[test]
+    fn sms_locale() {
+        let mut c = Client::new(API_URL, API_KEY);
+        c.retry_wait = 3000;
+        c.retry_count = 10;
+        let (status, user) = user::create(&c, "user@domain.com", 54, "317-338-9302", false).expect("User to be created");
+        assert!(status.success);
+
+        let options = TokenRequestOptions { force: true, locale: Some(Locale::PortugueseBR), ..TokenRequestOptions::default() };
+        let (status, sms) = user::sms(&c, user.id, &options).expect("Phone");
         assert!(status.success);
 
         assert_eq!(sms.cellphone, "+54-XXX-XXX-XX02");
     }
 
+    #[test]
+    fn sms_custom_code_invalid() {
+        let c = Client::new(API_URL, API_KEY);
+
+        let options = TokenRequestOptions { custom_code: Some("12ab"), ..TokenRequestOptions::default() };
+        match user::sms(&c, 1234567, &options) {
+            Err(AuthyError::InvalidRequest(_)) => (),
+            o => unreachable!("Expecting AuthyError::InvalidRequest, got: {:?}", o),
+        };
+    }
+
     #[test]
     fn call() {
         let mut c = Client::new(API_URL, API_KEY);
@@ -145,7 +211,7 @@ mod user {
         let (status, user) = user::create(&c, "user@domain.com", 54, "317-338-9302", false).expect("User to be created");
         assert!(status.success);
 
-        let (status, sms) = user::call(&c, user.id, false, None, None).expect("Phone");
+        let (status, sms) = user::call(&c, user.id, &TokenRequestOptions::default()).expect("Phone");
         assert!(status.success);
 
         assert_eq!(sms.cellphone, "+54-XXX-XXX-XX02");
@@ -159,12 +225,26 @@ mod user {
         let (status, user) = user::create(&c, "user@domain.com", 54, "317-338-9302", false).expect("User to be created");
         assert!(status.success);
 
-        let (status, sms) = user::call(&c, user.id, true, Some("an_action"), Some("a_message")).expect("Phone");
+        let (status, sms) = user::call(&c, user.id, &action_options()).expect("Phone");
         assert!(status.success);
 
         assert_eq!(sms.cellphone, "+54-XXX-XXX-XX02");
     }
 
+    #[test]
+    fn generate_qr() {
+        let mut c = Client::new(API_URL, API_KEY);
+        c.retry_wait = 3000;
+        c.retry_count = 10;
+        let (status, user) = user::create(&c, "user@domain.com", 54, "317-338-9302", false).expect("User to be created");
+        assert!(status.success);
+
+        let (status, qr) = user::generate_qr(&c, user.id, Some("user@domain.com"), Some(200)).expect("QrCode");
+        assert!(status.success);
+
+        assert!(qr.qr_code.starts_with("https://"));
+    }
+
     #[test]
     #[ignore]
     // This works with my real API key, just not on the sandbox
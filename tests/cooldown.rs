@@ -0,0 +1,104 @@
+extern crate authy;
+
+#[cfg(test)]
+mod cooldown {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::authy::{AuthyError, Store, MemoryStore, PhoneCall, SendCooldown, CooldownMode, SendOutcome};
+
+    fn cooldown() -> SendCooldown {
+        SendCooldown::new(Arc::new(MemoryStore::new()), Duration::from_secs(60))
+    }
+
+    fn sent() -> Result<PhoneCall, AuthyError> {
+        Ok(PhoneCall { cellphone: "+54-317-338-9302".into(), ..PhoneCall::default() })
+    }
+
+    #[test]
+    fn acquire() {
+        let cooldown = cooldown();
+        assert_eq!(cooldown.mode, CooldownMode::Reject);
+        assert_eq!(cooldown.time_until_send(1).unwrap(), Duration::from_secs(0));
+
+        cooldown.acquire(1).expect("First send");
+        let wait = cooldown.time_until_send(1).unwrap();
+        assert!(wait > Duration::from_secs(58) && wait <= Duration::from_secs(60), "wait: {:?}", wait);
+
+        match cooldown.acquire(1) {
+            Err(AuthyError::ResendCooldown(d)) => assert!(d > Duration::from_secs(58)),
+            o => unreachable!("Expecting AuthyError::ResendCooldown, got: {:?}", o),
+        };
+
+        // Each user has their own cooldown.
+        cooldown.acquire(2).expect("Other user");
+    }
+
+    #[test]
+    fn release() {
+        let cooldown = cooldown();
+        cooldown.acquire(1).unwrap();
+        cooldown.release(1).unwrap();
+
+        assert_eq!(cooldown.time_until_send(1).unwrap(), Duration::from_secs(0));
+        cooldown.acquire(1).expect("Send after release");
+    }
+
+    #[test]
+    fn send_reject() {
+        let cooldown = cooldown();
+        assert_eq!(cooldown.send(1, sent), Ok(SendOutcome::Sent(sent().unwrap())));
+
+        match cooldown.send(1, || unreachable!("Sent during the cooldown")) {
+            Err(AuthyError::ResendCooldown(d)) => assert!(d > Duration::from_secs(58)),
+            o => unreachable!("Expecting AuthyError::ResendCooldown, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn send_coalesce() {
+        let cooldown = cooldown().mode(CooldownMode::Coalesce);
+        assert_eq!(cooldown.send(1, sent), Ok(SendOutcome::Sent(sent().unwrap())));
+
+        match cooldown.send(1, || unreachable!("Sent during the cooldown")) {
+            Ok(SendOutcome::Coalesced(d)) => assert!(d > Duration::from_secs(58) && d <= Duration::from_secs(60), "wait: {:?}", d),
+            o => unreachable!("Expecting SendOutcome::Coalesced, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn send_failed() {
+        let cooldown = cooldown();
+        assert_eq!(cooldown.send(1, || Err(AuthyError::ServiceUnavailable)), Err(AuthyError::ServiceUnavailable));
+
+        // The failed send doesn't start the cooldown.
+        assert_eq!(cooldown.time_until_send(1).unwrap(), Duration::from_secs(0));
+        assert_eq!(cooldown.send(1, sent), Ok(SendOutcome::Sent(sent().unwrap())));
+    }
+
+    #[test]
+    fn short_window() {
+        let cooldown = SendCooldown::new(Arc::new(MemoryStore::new()), Duration::from_millis(1500));
+        cooldown.acquire(1).unwrap();
+
+        // While the send is remembered there is always time left.
+        match cooldown.acquire(1) {
+            Err(AuthyError::ResendCooldown(d)) => {
+                assert!(d > Duration::from_secs(0) && d <= Duration::from_millis(1500), "wait: {:?}", d);
+                assert!(cooldown.time_until_send(1).unwrap() > Duration::from_secs(0));
+            },
+            o => unreachable!("Expecting AuthyError::ResendCooldown, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn shared_store() {
+        let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+        let a = SendCooldown::new(store.clone(), Duration::from_secs(60));
+        let b = SendCooldown::new(store, Duration::from_secs(60)).mode(CooldownMode::Coalesce);
+
+        a.acquire(1).unwrap();
+        assert!(b.time_until_send(1).unwrap() > Duration::from_secs(0));
+        assert!(b.acquire(1).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+extern crate authy;
+#[macro_use]
+extern crate serde_json;
+extern crate serde_urlencoded;
+
+#[cfg(test)]
+mod form {
+    use std::collections::BTreeMap;
+
+    use super::authy::{Form, Param};
+
+    fn encode(form: &Form) -> String {
+        serde_urlencoded::to_string(form.params()).expect("Encoded form")
+    }
+
+    #[test]
+    fn fields() {
+        let mut form = Form::new();
+        form.field("message", "Login requested")
+            .field("seconds_to_expire", 120)
+            .optional("locale", None::<&str>)
+            .optional("force", Some(true));
+
+        assert_eq!(encode(&form), "message=Login+requested&seconds_to_expire=120&force=true");
+    }
+
+    #[test]
+    fn map() {
+        let mut form = Form::new();
+        form.map("user", vec![
+            ("email", "user@domain.com"),
+            ("cellphone", "317-338-9302"),
+            ("country_code", "54"),
+        ]);
+
+        assert_eq!(encode(&form), "user%5Bemail%5D=user%40domain.com&user%5Bcellphone%5D=317-338-9302&user%5Bcountry_code%5D=54");
+    }
+
+    #[test]
+    fn map_ordered() {
+        let mut details = BTreeMap::new();
+        details.insert("Recipient", "Bob".to_string());
+        details.insert("Amount", "$10".to_string());
+
+        let mut form = Form::new();
+        form.nested("details", &details);
+
+        assert_eq!(form.params(), &[
+            ("details[Amount]".to_string(), "$10".to_string()),
+            ("details[Recipient]".to_string(), "Bob".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn list_of_maps() {
+        let mut form = Form::new();
+        form.nested("logos", Param::list(vec![
+            Param::map(vec![("res", "default"), ("url", "https://example.com/default.png")]),
+            Param::map(vec![("res", "low"), ("url", "https://example.com/low.png")]),
+        ]));
+
+        assert_eq!(encode(&form), "logos%5B%5D%5Bres%5D=default&logos%5B%5D%5Burl%5D=https%3A%2F%2Fexample.com%2Fdefault.png&logos%5B%5D%5Bres%5D=low&logos%5B%5D%5Burl%5D=https%3A%2F%2Fexample.com%2Flow.png");
+    }
+
+    #[test]
+    fn list_of_values() {
+        let mut form = Form::new();
+        form.nested("devices", Param::list(vec!["ios", "android"]));
+
+        assert_eq!(form.params(), &[
+            ("devices[]".to_string(), "ios".to_string()),
+            ("devices[]".to_string(), "android".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn json() {
+        let value = json!({
+            "approval_request": {
+                "transaction": {
+                    "details": { "Amount": "$10" },
+                    "encrypted": false,
+                    "reason": null,
+                },
+                "expiration_timestamp": 1455911778,
+            },
+            "authy_id": 1234,
+        });
+
+        let mut form = Form::new();
+        form.nested("params", &value);
+
+        assert_eq!(form.params(), &[
+            ("params[approval_request][expiration_timestamp]".to_string(), "1455911778".to_string()),
+            ("params[approval_request][transaction][details][Amount]".to_string(), "$10".to_string()),
+            ("params[approval_request][transaction][encrypted]".to_string(), "false".to_string()),
+            ("params[approval_request][transaction][reason]".to_string(), "".to_string()),
+            ("params[authy_id]".to_string(), "1234".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn empty() {
+        let mut form = Form::new();
+        form.nested("details", Param::map(Vec::<(&str, &str)>::new()))
+            .nested("logos", Param::list(Vec::<Param>::new()));
+
+        assert!(form.params().is_empty());
+    }
+}
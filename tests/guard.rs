@@ -0,0 +1,145 @@
+extern crate authy;
+
+#[cfg(test)]
+mod guard {
+    use std::sync::Arc;
+
+    use super::authy::{AuthyError, Guard, GuardConfig, MemoryStore};
+    use super::authy::guard::{Attempt, Limit, Violation};
+
+    fn guard() -> Guard {
+        let config = GuardConfig::from_toml(include_str!("fixtures/guard.toml")).expect("GuardConfig");
+        Guard::new(config, Arc::new(MemoryStore::new()))
+    }
+
+    fn attempt<'a>(country_code: u16, phone: &'a str) -> Attempt<'a> {
+        Attempt { country_code, phone, authy_id: None, user_ip: None }
+    }
+
+    fn expect_violation(r: Result<(), AuthyError>, expected: Violation) {
+        match r {
+            Err(AuthyError::GuardViolation(ref v)) if *v == expected => (),
+            o => unreachable!("Expecting {:?}, got: {:?}", expected, o),
+        };
+    }
+
+    #[test]
+    fn from_toml() {
+        let config = GuardConfig::from_toml(include_str!("fixtures/guard.toml")).expect("GuardConfig");
+        assert_eq!(config.allowed_country_codes, vec![1, 44]);
+        assert_eq!(config.per_user, Some(Limit { max: 2, window_secs: 3600 }));
+        assert_eq!(config.prefix_limits[0].prefix, "+44 70");
+        assert_eq!(config.global, None);
+    }
+
+    #[test]
+    fn from_toml_unknown_key() {
+        match GuardConfig::from_toml("per_country = { max = 1, window = 60 }") {
+            Err(AuthyError::ConfigError(_)) => (),
+            o => unreachable!("Expecting AuthyError::ConfigError, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn country_not_allowed() {
+        expect_violation(guard().check(&attempt(882, "1234567")), Violation::CountryNotAllowed(882));
+    }
+
+    #[test]
+    fn country_limit() {
+        let guard = guard();
+        for _ in 0..3 {
+            guard.check(&attempt(44, "20 7946 0000")).expect("Allowed");
+        }
+        expect_violation(guard.check(&attempt(44, "20 7946 0001")), Violation::CountryLimit(44));
+
+        // Country limits replace the default.
+        for _ in 0..5 {
+            guard.check(&attempt(1, "949-555-1234")).expect("Allowed");
+        }
+    }
+
+    #[test]
+    fn prefix_limit() {
+        let guard = guard();
+        guard.check(&attempt(44, "7000 000001")).expect("Allowed");
+        expect_violation(guard.check(&attempt(44, "7000 000002")), Violation::PrefixLimit("4470".into()));
+
+        guard.check(&attempt(44, "7100 000001")).expect("Allowed");
+    }
+
+    #[test]
+    fn user_limit() {
+        let guard = guard();
+        let a = Attempt { authy_id: Some(42), ..attempt(1, "949-555-1234") };
+        guard.check(&a).expect("Allowed");
+        guard.check(&a).expect("Allowed");
+        expect_violation(guard.check(&a), Violation::UserLimit(42));
+
+        guard.check(&Attempt { authy_id: Some(43), ..a }).expect("Allowed");
+    }
+
+    #[test]
+    fn ip_limits() {
+        let guard = guard();
+        let a = Attempt { user_ip: Some("10.0.0.1"), ..attempt(1, "949-555-1234") };
+        let b = Attempt { phone: "949-555-1235", ..a };
+
+        // The same numbers can be sent to again.
+        guard.check(&a).expect("Allowed");
+        guard.check(&b).expect("Allowed");
+        guard.check(&a).expect("Allowed");
+        expect_violation(guard.check(&Attempt { phone: "949-555-1236", ..a }), Violation::NumbersPerIp("10.0.0.1".into()));
+
+        expect_violation(guard.check(&b), Violation::IpLimit("10.0.0.1".into()));
+        guard.check(&Attempt { user_ip: Some("10.0.0.2"), ..a }).expect("Allowed");
+    }
+
+    #[test]
+    fn numbers_per_ip_retry() {
+        let config = GuardConfig { numbers_per_ip: Some(Limit { max: 1, window_secs: 60 }), ..GuardConfig::default() };
+        let guard = Guard::new(config, Arc::new(MemoryStore::new()));
+        let a = Attempt { user_ip: Some("10.0.0.1"), ..attempt(1, "949-555-1234") };
+        let b = Attempt { phone: "949-555-1235", ..a };
+
+        guard.check(&a).expect("Allowed");
+        expect_violation(guard.check(&b), Violation::NumbersPerIp("10.0.0.1".into()));
+        expect_violation(guard.check(&b), Violation::NumbersPerIp("10.0.0.1".into()));
+        guard.check(&a).expect("Allowed");
+    }
+
+    #[test]
+    fn narrowest_first() {
+        let config = GuardConfig {
+            per_ip: Some(Limit { max: 1, window_secs: 60 }),
+            global: Some(Limit { max: 2, window_secs: 60 }),
+            ..GuardConfig::default()
+        };
+        let guard = Guard::new(config, Arc::new(MemoryStore::new()));
+        let a = Attempt { user_ip: Some("10.0.0.1"), ..attempt(1, "949-555-1234") };
+
+        // Refusing one IP address doesn't use up the global limit.
+        guard.check(&a).expect("Allowed");
+        for _ in 0..5 {
+            expect_violation(guard.check(&a), Violation::IpLimit("10.0.0.1".into()));
+        }
+        guard.check(&Attempt { user_ip: Some("10.0.0.2"), ..a }).expect("Allowed");
+        expect_violation(guard.check(&Attempt { user_ip: Some("10.0.0.3"), ..a }), Violation::GlobalLimit);
+    }
+
+    #[test]
+    fn global_limit() {
+        let config = GuardConfig { global: Some(Limit { max: 1, window_secs: 60 }), ..GuardConfig::default() };
+        let guard = Guard::new(config, Arc::new(MemoryStore::new()));
+        guard.check(&attempt(1, "949-555-1234")).expect("Allowed");
+        expect_violation(guard.check(&attempt(33, "1 23 45 67 89")), Violation::GlobalLimit);
+    }
+
+    #[test]
+    fn unlimited() {
+        let guard = Guard::new(GuardConfig::default(), Arc::new(MemoryStore::new()));
+        for _ in 0..100 {
+            guard.check(&attempt(882, "1234567")).expect("Allowed");
+        }
+    }
+}
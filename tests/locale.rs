@@ -0,0 +1,73 @@
+extern crate authy;
+extern crate serde_json;
+
+#[cfg(test)]
+mod locale {
+    use serde_json;
+
+    use super::authy::{AuthyError, Locale};
+    use super::authy::api::phone::PhoneStartOptions;
+
+    #[test]
+    fn parse() {
+        assert_eq!("pt-BR".parse(), Ok(Locale::PortugueseBR));
+        assert_eq!("pt_br".parse(), Ok(Locale::PortugueseBR));
+        assert_eq!(" DE ".parse(), Ok(Locale::German));
+        assert_eq!("en-GB".parse(), Ok(Locale::EnglishGB));
+
+        match "klingon".parse::<Locale>() {
+            Err(AuthyError::InvalidRequest(_)) => (),
+            o => unreachable!("Expecting AuthyError::InvalidRequest, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn fallback() {
+        assert_eq!(Locale::from_tag("de-CH"), Some(Locale::German));
+        assert_eq!(Locale::from_tag("pt-PT"), Some(Locale::Portuguese));
+        assert_eq!(Locale::from_tag("zh-Hant-TW"), Some(Locale::ChineseTraditional));
+        assert_eq!(Locale::from_tag("zh-Hans-CN"), Some(Locale::ChineseSimplified));
+        assert_eq!(Locale::from_tag("zh-Latn"), Some(Locale::Chinese));
+        assert_eq!(Locale::from_tag("nn-NO"), Some(Locale::Norwegian));
+        assert_eq!(Locale::from_tag("x-klingon"), None);
+        assert_eq!(Locale::from_tag(""), None);
+    }
+
+    #[test]
+    fn negotiate() {
+        assert_eq!(Locale::negotiate(vec!["tlh", "de-AT", "en"]), Some(Locale::German));
+        assert_eq!(Locale::negotiate(vec!["tlh"]), None);
+    }
+
+    #[test]
+    fn tag() {
+        assert_eq!(Locale::PortugueseBR.tag(), "pt-BR");
+        assert_eq!(Locale::ChineseTraditional.to_string(), "zh-HK");
+
+        for tag in &["af", "zh-CN", "en-GB", "nb", "pt-BR", "vi"] {
+            assert_eq!(Locale::from_tag(tag).expect("Locale").tag(), *tag);
+        }
+    }
+
+    #[test]
+    fn serde() {
+        assert_eq!(serde_json::to_string(&Locale::PortugueseBR).expect("Serialized"), r#""pt-BR""#);
+        assert_eq!(serde_json::from_str::<Locale>(r#""de_AT""#).expect("Locale"), Locale::German);
+        assert!(serde_json::from_str::<Locale>(r#""klingon""#).is_err());
+    }
+
+    #[test]
+    fn country_code() {
+        assert_eq!(Locale::from_country_code(55), Some(Locale::PortugueseBR));
+        assert_eq!(Locale::from_country_code(49), Some(Locale::German));
+        assert_eq!(Locale::from_country_code(41), None);
+    }
+
+    #[test]
+    fn infer_locale() {
+        assert_eq!(PhoneStartOptions::new().locale_for(49), None);
+        assert_eq!(PhoneStartOptions::new().infer_locale().locale_for(49), Some(Locale::German));
+        assert_eq!(PhoneStartOptions::new().infer_locale().locale_for(41), None);
+        assert_eq!(PhoneStartOptions::new().infer_locale().locale(Locale::French).locale_for(49), Some(Locale::French));
+    }
+}
@@ -0,0 +1,351 @@
+extern crate authy;
+#[macro_use]
+extern crate serde_json;
+
+#[cfg(test)]
+mod onetouch {
+    const API_URL: &str = "https://sandbox-api.authy.com";
+    const API_KEY: &str = "bf12974d70818a08199d17d5e2bae630";
+
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use super::authy::{AuthyError, Client, OneTouch, ApprovalOutcome};
+    use super::authy::onetouch::{ApprovalStatus, Cancel, WaitOptions};
+
+    // Waits on a request that reports each of `statuses` in turn, returning
+    // the outcome and how long it took.
+    fn wait(options: &WaitOptions, statuses: &[ApprovalStatus]) -> (Result<ApprovalOutcome, AuthyError>, Duration) {
+        let mut o = OneTouch::default();
+        let mut statuses = statuses.iter();
+        let start = Instant::now();
+        let outcome = o.wait_with(options, |o| {
+            o.status = statuses.next().cloned().unwrap_or(ApprovalStatus::Pending);
+            Ok(())
+        });
+        (outcome, start.elapsed())
+    }
+
+    #[test]
+    fn cancel_shared() {
+        let cancel = Cancel::new();
+        let other = cancel.clone();
+        assert!(!other.is_cancelled());
+
+        thread::spawn(move || cancel.cancel()).join().expect("Cancelled");
+        assert!(other.is_cancelled());
+    }
+
+    #[test]
+    fn wait_cancelled() {
+        let c = Client::new(API_URL, API_KEY);
+        let cancel = Cancel::new();
+        cancel.cancel();
+
+        let mut o = OneTouch { uuid: "550e8400-e29b-41d4-a716-446655440000".into(), ..OneTouch::default() };
+        let options = WaitOptions {
+            timeout: Some(Duration::from_secs(1)),
+            cancel: Some(cancel),
+            ..WaitOptions::default()
+        };
+        assert_eq!(o.wait_for_approval(&c, &options), Ok(ApprovalOutcome::Cancelled));
+    }
+
+    #[test]
+    fn wait_terminal() {
+        let options = WaitOptions::default();
+        for &(status, outcome) in &[
+            (ApprovalStatus::Approved, ApprovalOutcome::Approved),
+            (ApprovalStatus::Denied, ApprovalOutcome::Denied),
+            (ApprovalStatus::Expired, ApprovalOutcome::Expired),
+        ] {
+            let (result, elapsed) = wait(&options, &[status]);
+            assert_eq!(result, Ok(outcome));
+            assert!(elapsed < Duration::from_secs(1));
+        }
+    }
+
+    #[test]
+    fn wait_backoff() {
+        let options = WaitOptions { max_interval: Duration::from_secs(2), ..WaitOptions::default() };
+        let (result, elapsed) = wait(&options, &[ApprovalStatus::Pending, ApprovalStatus::Unknown, ApprovalStatus::Pending, ApprovalStatus::Denied]);
+
+        // Waits of 1, 2 and 2 seconds.
+        assert_eq!(result, Ok(ApprovalOutcome::Denied));
+        assert!(elapsed >= Duration::from_secs(5));
+        assert!(elapsed < Duration::from_secs(6));
+    }
+
+    #[test]
+    fn wait_min_interval() {
+        let options = WaitOptions { interval: Duration::from_secs(0), ..WaitOptions::default() };
+        let (result, elapsed) = wait(&options, &[ApprovalStatus::Pending, ApprovalStatus::Approved]);
+
+        assert_eq!(result, Ok(ApprovalOutcome::Approved));
+        assert!(elapsed >= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn wait_timed_out() {
+        let options = WaitOptions { timeout: Some(Duration::from_millis(1500)), ..WaitOptions::default() };
+        let (result, elapsed) = wait(&options, &[]);
+
+        assert_eq!(result, Ok(ApprovalOutcome::TimedOut));
+        assert!(elapsed >= Duration::from_millis(1500));
+        assert!(elapsed < Duration::from_millis(2500));
+    }
+
+    #[test]
+    fn wait_invalid() {
+        let options = WaitOptions { interval: Duration::from_secs(5), max_interval: Duration::from_secs(2), ..WaitOptions::default() };
+        match wait(&options, &[ApprovalStatus::Approved]).0 {
+            Err(AuthyError::InvalidRequest(_)) => (),
+            other => panic!("Expected InvalidRequest, got {:?}", other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod callback {
+    const API_KEY: &str = "bf12974d70818a08199d17d5e2bae630";
+    const URL: &str = "https://example.com/authy/callback";
+    const NONCE: &str = "1455825429";
+    const SIGNATURE: &str = "ffDhbHKMnTPhP38ryPtsOLrI+F+rXCwL43o9Y78xzNI=";
+
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use serde_json::{self, Value};
+
+    use super::authy::AuthyError;
+    use super::authy::onetouch::ApprovalStatus;
+    use super::authy::onetouch::callback::{self, REPLAY_WINDOW};
+
+    fn params() -> Value {
+        serde_json::from_str(include_str!("fixtures/onetouch_callback.json")).expect("Valid json")
+    }
+
+    #[test]
+    fn sign() {
+        assert_eq!(callback::sign(API_KEY, "POST", URL, &params(), NONCE), SIGNATURE);
+    }
+
+    #[test]
+    fn verify_signature() {
+        assert_eq!(callback::verify_signature(API_KEY, "post", URL, &params(), NONCE, SIGNATURE), Ok(()));
+    }
+
+    #[test]
+    fn verify_signature_tampered() {
+        let mut params = params();
+        params["status"] = "denied".into();
+
+        match callback::verify_signature(API_KEY, "POST", URL, &params, NONCE, SIGNATURE) {
+            Err(AuthyError::InvalidSignature(_)) => (),
+            o => unreachable!("Expecting AuthyError::InvalidSignature, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn verify_signature_wrong_key() {
+        match callback::verify_signature("not the api key", "POST", URL, &params(), NONCE, SIGNATURE) {
+            Err(AuthyError::InvalidSignature(_)) => (),
+            o => unreachable!("Expecting AuthyError::InvalidSignature, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn verify_signature_not_base64() {
+        match callback::verify_signature(API_KEY, "POST", URL, &params(), NONCE, "not base64!") {
+            Err(AuthyError::InvalidSignature(_)) => (),
+            o => unreachable!("Expecting AuthyError::InvalidSignature, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn verify_nonce() {
+        let now = UNIX_EPOCH + Duration::from_secs(1455825429 + 60);
+        assert_eq!(callback::verify_nonce(NONCE, now, REPLAY_WINDOW), Ok(()));
+        assert_eq!(callback::verify_nonce("1455825429.7234", now, REPLAY_WINDOW), Ok(()));
+
+        let later = now + REPLAY_WINDOW;
+        match callback::verify_nonce(NONCE, later, REPLAY_WINDOW) {
+            Err(AuthyError::InvalidSignature(_)) => (),
+            o => unreachable!("Expecting AuthyError::InvalidSignature, got: {:?}", o),
+        };
+        match callback::verify_nonce("yesterday", now, REPLAY_WINDOW) {
+            Err(AuthyError::InvalidSignature(_)) => (),
+            o => unreachable!("Expecting AuthyError::InvalidSignature, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn verify_stale() {
+        match callback::verify(API_KEY, "POST", URL, &params(), NONCE, SIGNATURE) {
+            Err(AuthyError::InvalidSignature(_)) => (),
+            o => unreachable!("Expecting AuthyError::InvalidSignature, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn parse() {
+        let event = callback::parse(include_bytes!("fixtures/onetouch_callback.json")).expect("ApprovalEvent");
+        assert_eq!(event.uuid, "550e8400-e29b-41d4-a716-446655440000");
+        assert_eq!(event.status, ApprovalStatus::Approved);
+        assert_eq!(event.authy_id, 1234567);
+
+        let transaction = event.approval_request.expect("ApprovalEventRequest").transaction;
+        assert_eq!(transaction.message, "Login requested");
+        assert_eq!(transaction.details["Username"], "Bill Smith");
+        assert_eq!(transaction.hidden_details["ip_address"], "10.10.3.203");
+        assert!(transaction.extra.contains_key("customer_uuid"));
+    }
+}
+
+#[cfg(test)]
+mod transaction {
+    const KEY: &[u8] = b"transaction digest key";
+
+    use serde_json::{self, Value};
+
+    use super::authy::AuthyError;
+    use super::authy::onetouch::{ApprovalRequest, ApprovalTransaction, Transaction, DIGEST_KEY};
+    use super::authy::onetouch::callback;
+
+    fn transfer() -> Transaction {
+        Transaction::new("Approve transfer to Jane Doe")
+            .detail("Amount", "$1,000.00")
+            .detail("Recipient", "Jane Doe")
+            .hidden_detail("account", "12345678")
+    }
+
+    // The status of `transfer()` as returned by Authy.
+    fn echoed() -> ApprovalRequest {
+        let res: Value = serde_json::from_str(include_str!("fixtures/approval_request_transaction.json")).expect("Fixture");
+        serde_json::from_value(res["approval_request"].clone()).expect("ApprovalRequest")
+    }
+
+    fn details(request: &mut ApprovalRequest) -> &mut ApprovalTransaction {
+        request.transaction.as_mut().expect("ApprovalTransaction")
+    }
+
+    #[test]
+    fn digest_canonical() {
+        let reordered = Transaction::new("Approve transfer to Jane Doe")
+            .hidden_detail("account", "12345678")
+            .detail("Recipient", "Jane Doe")
+            .detail("Amount", "$1,000.00");
+        assert_eq!(transfer().digest(KEY), reordered.digest(KEY));
+
+        let moved = Transaction::new("Approve transfer to Jane Doe")
+            .detail("Amount", "$1,000.00")
+            .detail("Recipient", "Jane Doe")
+            .detail("account", "12345678");
+        assert_ne!(transfer().digest(KEY), moved.digest(KEY));
+        assert_ne!(transfer().digest(KEY), transfer().digest(b"another key"));
+    }
+
+    #[test]
+    fn from_details() {
+        let details = json!({"Amount": 1000, "Recipient": "Jane Doe", "Memo": null});
+        let transaction = Transaction::from_details("Approve transfer to Jane Doe", &details).expect("Transaction");
+
+        assert_eq!(transaction.details["Amount"], "1000");
+        assert_eq!(transaction.details["Recipient"], "Jane Doe");
+        assert!(!transaction.details.contains_key("Memo"));
+
+        match Transaction::from_details("Approve transfer", &vec![1, 2]) {
+            Err(AuthyError::InvalidRequest(_)) => (),
+            o => unreachable!("Expecting AuthyError::InvalidRequest, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn request() {
+        assert_eq!(transfer().request(KEY).validate(), Ok(()));
+    }
+
+    #[test]
+    fn verify_request() {
+        let request = echoed();
+        assert_eq!(request.transaction.as_ref().map(|t| t.reason.as_deref()), Some(Some("Transfer")));
+        assert_eq!(transfer().verify_request(&request, KEY), Ok(()));
+    }
+
+    #[test]
+    fn verify_request_wrong_key() {
+        assert_eq!(transfer().verify_request(&echoed(), b"another key"), Err(AuthyError::TransactionMismatch(format!("hidden_details[{}]", DIGEST_KEY))));
+    }
+
+    #[test]
+    fn verify_request_forged() {
+        // Changing the details and the unkeyed parts together still fails.
+        let forged = transfer().detail("Amount", "$9,000.00");
+        let mut request = echoed();
+        details(&mut request).details.insert("Amount".into(), "$9,000.00".into());
+        details(&mut request).hidden_details.insert(DIGEST_KEY.into(), forged.digest(b"guessed key"));
+
+        assert_eq!(forged.verify_request(&request, KEY), Err(AuthyError::TransactionMismatch(format!("hidden_details[{}]", DIGEST_KEY))));
+    }
+
+    #[test]
+    fn verify_request_tampered() {
+        let mut request = echoed();
+        details(&mut request).details.insert("Amount".into(), "$9,000.00".into());
+
+        assert_eq!(transfer().verify_request(&request, KEY), Err(AuthyError::TransactionMismatch("details[Amount]".into())));
+    }
+
+    #[test]
+    fn verify_request_missing() {
+        let transaction = transfer();
+
+        let mut request = echoed();
+        details(&mut request).details.remove("Recipient");
+        assert_eq!(transaction.verify_request(&request, KEY), Err(AuthyError::TransactionMismatch("details[Recipient] is missing".into())));
+
+        let mut request = echoed();
+        details(&mut request).hidden_details.remove(DIGEST_KEY);
+        match transaction.verify_request(&request, KEY) {
+            Err(AuthyError::TransactionMismatch(_)) => (),
+            o => unreachable!("Expecting AuthyError::TransactionMismatch, got: {:?}", o),
+        };
+
+        let mut request = echoed();
+        request.transaction = None;
+        match transaction.verify_request(&request, KEY) {
+            Err(AuthyError::TransactionMismatch(_)) => (),
+            o => unreachable!("Expecting AuthyError::TransactionMismatch, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn verify_event() {
+        let event = callback::parse(include_bytes!("fixtures/onetouch_callback.json")).expect("ApprovalEvent");
+        let transaction = Transaction::new("Login requested")
+            .detail("Location", "California, USA")
+            .detail("Username", "Bill Smith")
+            .hidden_detail("ip_address", "10.10.3.203");
+
+        // The fixture was not sent with a digest.
+        match transaction.verify_event(&event, KEY) {
+            Err(AuthyError::TransactionMismatch(message)) => assert!(message.contains(DIGEST_KEY)),
+            o => unreachable!("Expecting AuthyError::TransactionMismatch, got: {:?}", o),
+        };
+
+        let mut event = event;
+        event.approval_request.as_mut().expect("ApprovalEventRequest").transaction
+            .hidden_details.insert(DIGEST_KEY.into(), transaction.digest(KEY));
+        assert_eq!(transaction.verify_event(&event, KEY), Ok(()));
+
+        let other = transaction.clone().detail("Username", "Jane Doe");
+        assert_eq!(other.verify_event(&event, KEY), Err(AuthyError::TransactionMismatch("details[Username]".into())));
+    }
+
+    #[test]
+    fn serialize() {
+        let transaction = transfer();
+        let value = serde_json::to_string(&transaction).expect("Serialized");
+        let back: Transaction = serde_json::from_str(&value).expect("Transaction");
+        assert_eq!(back.digest(KEY), transaction.digest(KEY));
+    }
+}
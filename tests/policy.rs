@@ -0,0 +1,113 @@
+extern crate authy;
+
+#[cfg(test)]
+mod policy {
+    use super::authy::{AuthyError, Phone, ContactType, PhonePolicy, Decision};
+    use super::authy::policy::{Action, Reason};
+
+    fn policy() -> PhonePolicy {
+        PhonePolicy::from_toml(include_str!("fixtures/phone_policy.toml")).expect("PhonePolicy")
+    }
+
+    fn phone(phone_type: &str) -> Phone {
+        Phone {
+            country_code: 1,
+            phone_number: "949-555-1234".into(),
+            phone_type: phone_type.into(),
+            provider: Some("AT&T Wireless".into()),
+            ported: false,
+        }
+    }
+
+    #[test]
+    fn from_toml() {
+        let policy = policy();
+        assert_eq!(policy.voip, Some(Action::Deny));
+        assert_eq!(policy.unknown_type, Some(Action::StepUp));
+        assert_eq!(policy.blocked_country_codes, vec![7, 86]);
+    }
+
+    #[test]
+    fn from_toml_unknown_rule() {
+        match PhonePolicy::from_toml(r#"reject_voip = "deny""#) {
+            Err(AuthyError::ConfigError(_)) => (),
+            o => unreachable!("Expecting AuthyError::ConfigError, got: {:?}", o),
+        };
+        match PhonePolicy::from_toml(r#"voip = "maybe""#) {
+            Err(AuthyError::ConfigError(_)) => (),
+            o => unreachable!("Expecting AuthyError::ConfigError, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn from_file_missing() {
+        match PhonePolicy::from_file("tests/fixtures/does_not_exist.toml") {
+            Err(AuthyError::IoError(_)) => (),
+            o => unreachable!("Expecting AuthyError::IoError, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn allow() {
+        let evaluation = policy().evaluate(&phone("cellphone"), ContactType::SMS);
+        assert_eq!(evaluation.decision, Decision::Allow);
+        assert!(evaluation.reasons.is_empty());
+        assert!(evaluation.is_allowed());
+
+        let evaluation = PhonePolicy::default().evaluate(&phone("voip"), ContactType::SMS);
+        assert!(evaluation.is_allowed());
+    }
+
+    #[test]
+    fn deny_voip() {
+        let evaluation = policy().evaluate(&phone("voip"), ContactType::Call);
+        assert_eq!(evaluation.decision, Decision::Deny);
+        assert_eq!(evaluation.reasons, vec![(Reason::Voip, Action::Deny)]);
+    }
+
+    #[test]
+    fn landline() {
+        let policy = policy();
+        assert_eq!(policy.evaluate(&phone("landline"), ContactType::SMS).decision, Decision::Deny);
+        assert_eq!(policy.evaluate(&phone("Landline"), ContactType::Call).decision, Decision::Allow);
+    }
+
+    #[test]
+    fn unknown_type() {
+        let evaluation = policy().evaluate(&phone("unknown"), ContactType::SMS);
+        assert_eq!(evaluation.decision, Decision::StepUp);
+        assert_eq!(evaluation.reasons, vec![(Reason::UnknownType, Action::StepUp)]);
+    }
+
+    #[test]
+    fn ported() {
+        let policy = policy();
+
+        let mut p = phone("cellphone");
+        p.ported = true;
+        let evaluation = policy.evaluate(&p, ContactType::SMS);
+        assert_eq!(evaluation.decision, Decision::StepUp);
+        assert_eq!(evaluation.reasons, vec![(Reason::Ported(Some("AT&T Wireless".into())), Action::StepUp)]);
+        assert_eq!(evaluation.reasons[0].0.to_string(), "ported number now with AT&T Wireless");
+
+        p.provider = Some("verizon wireless".into());
+        assert!(policy.evaluate(&p, ContactType::SMS).is_allowed());
+
+        p.provider = None;
+        assert_eq!(policy.evaluate(&p, ContactType::SMS).decision, Decision::StepUp);
+    }
+
+    #[test]
+    fn most_severe() {
+        let mut p = phone("unknown");
+        p.country_code = 86;
+        let evaluation = policy().evaluate(&p, ContactType::SMS);
+
+        assert_eq!(evaluation.decision, Decision::Deny);
+        assert_eq!(evaluation.reasons, vec![
+            (Reason::BlockedCountry(86), Action::Deny),
+            (Reason::UnknownType, Action::StepUp),
+        ]);
+        assert_eq!(evaluation.reasons[0].0.to_string(), "country code 86 is blocked");
+    }
+}
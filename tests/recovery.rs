@@ -0,0 +1,145 @@
+extern crate authy;
+extern crate base64;
+extern crate serde_json;
+
+#[cfg(test)]
+mod recovery {
+    // Far fewer than the default, to keep the tests quick.
+    const ROUNDS: u32 = 100;
+
+    use std::sync::Arc;
+
+    use serde_json::{self, Value};
+
+    use super::authy::{AuthyError, Client, Store, MemoryStore, RecoveryCodes, User, VerifyOptions, VerifyOutcome};
+    use super::authy::recovery::{self, CODE_LENGTH, DEFAULT_ROUNDS};
+
+    fn recovery_codes() -> RecoveryCodes {
+        RecoveryCodes::new(Arc::new(MemoryStore::new())).rounds(ROUNDS)
+    }
+
+    #[test]
+    fn generate() {
+        let recovery = recovery_codes().count(8);
+        let codes = recovery.generate(1).unwrap();
+
+        assert_eq!(codes.len(), 8);
+        for code in &codes {
+            assert_eq!(code.len(), CODE_LENGTH + 1);
+            assert_eq!(&code[5..6], "-");
+            assert!(recovery::is_recovery_code(code), "{}", code);
+        }
+        assert_eq!(recovery.remaining(1).unwrap(), 8);
+        assert_eq!(recovery.remaining(2).unwrap(), 0);
+    }
+
+    #[test]
+    fn verify_consumes() {
+        let recovery = recovery_codes();
+        let codes = recovery.generate(1).unwrap();
+
+        assert_eq!(recovery.verify(1, &codes[3]), Ok(VerifyOutcome::Valid));
+        assert_eq!(recovery.verify(1, &codes[3]), Ok(VerifyOutcome::Invalid));
+        assert_eq!(recovery.remaining(1).unwrap(), 9);
+
+        // Codes belong to a single user.
+        assert_eq!(recovery.verify(2, &codes[4]), Ok(VerifyOutcome::Invalid));
+        assert_eq!(recovery.verify(1, "aaaaa-aaaaa"), Ok(VerifyOutcome::Invalid));
+    }
+
+    #[test]
+    fn verify_normalizes() {
+        let recovery = recovery_codes();
+        let codes = recovery.generate(1).unwrap();
+
+        let entered = format!(" {} ", codes[0].to_uppercase().replace('-', " "));
+        assert_eq!(recovery.verify(1, &entered), Ok(VerifyOutcome::Valid));
+        assert_eq!(recovery.verify(1, &codes[1].replace('-', "")), Ok(VerifyOutcome::Valid));
+    }
+
+    #[test]
+    fn verify_or_recover_disabled() {
+        let recovery = recovery_codes();
+        let codes = recovery.generate(1).unwrap();
+
+        // Never called: a disabled user is refused before any lookup.
+        let c = Client::new("http://127.0.0.1:1", "unused");
+        let mut user = User { id: 1, account_disabled: true, ..User::default() };
+
+        let outcome = user.verify_or_recover(&c, &codes[0], &VerifyOptions::default(), &recovery);
+        assert_eq!(outcome, Ok(VerifyOutcome::UserDisabled));
+        assert_eq!(recovery.remaining(1).unwrap(), 10);
+    }
+
+    #[test]
+    fn regenerate() {
+        let recovery = recovery_codes();
+        let old = recovery.generate(1).unwrap();
+        recovery.verify(1, &old[0]).unwrap();
+
+        let new = recovery.generate(1).unwrap();
+        assert_eq!(recovery.remaining(1).unwrap(), 10);
+        assert_eq!(recovery.verify(1, &old[1]), Ok(VerifyOutcome::Invalid));
+        assert_eq!(recovery.verify(1, &new[0]), Ok(VerifyOutcome::Valid));
+    }
+
+    #[test]
+    fn revoke() {
+        let recovery = recovery_codes();
+        let codes = recovery.generate(1).unwrap();
+        recovery.revoke(1).unwrap();
+
+        assert_eq!(recovery.remaining(1).unwrap(), 0);
+        assert_eq!(recovery.verify(1, &codes[0]), Ok(VerifyOutcome::Invalid));
+    }
+
+    #[test]
+    fn stored_set() {
+        let store = Arc::new(MemoryStore::new());
+        let recovery = RecoveryCodes::new(store.clone()).count(3).rounds(ROUNDS);
+        let codes = recovery.generate(1).unwrap();
+
+        let stored = store.get("authy:recovery:1").unwrap().expect("Stored codes");
+        for code in &codes {
+            assert!(!stored.contains(&code.replace('-', "")));
+        }
+
+        let set: Value = serde_json::from_str(&stored).expect("Code set");
+        assert_eq!(set["rounds"], ROUNDS);
+
+        let hashed = set["codes"].as_array().expect("Codes");
+        assert_eq!(hashed.len(), 3);
+        assert!(hashed[0]["salt"] != hashed[1]["salt"]);
+        for h in hashed {
+            assert_eq!(base64::decode(h["salt"].as_str().unwrap()).unwrap().len(), 16);
+            assert_eq!(base64::decode(h["hash"].as_str().unwrap()).unwrap().len(), 32);
+        }
+    }
+
+    #[test]
+    fn rounds() {
+        // Codes keep the rounds they were generated with.
+        let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+        let codes = RecoveryCodes::new(store.clone()).count(1).generate(1).unwrap();
+
+        let recovery = RecoveryCodes::new(store).rounds(ROUNDS);
+        assert_eq!(recovery.rounds, ROUNDS);
+        assert_eq!(recovery.verify(1, &codes[0]), Ok(VerifyOutcome::Valid));
+        assert_eq!(RecoveryCodes::new(Arc::new(MemoryStore::new())).rounds, DEFAULT_ROUNDS);
+
+        match recovery.rounds(0).generate(1) {
+            Err(AuthyError::InvalidRequest(_)) => (),
+            o => unreachable!("Expecting AuthyError::InvalidRequest, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn is_recovery_code() {
+        assert!(recovery::is_recovery_code("7kq4m-x2hvp"));
+        assert!(recovery::is_recovery_code("7KQ4MX2HVP"));
+        assert!(!recovery::is_recovery_code("0000000"));
+        assert!(!recovery::is_recovery_code("2345678923"));
+        assert!(!recovery::is_recovery_code("7kq4m-x2hv"));
+        assert!(!recovery::is_recovery_code("7kq4m-x2hvo"));
+    }
+}
@@ -0,0 +1,94 @@
+extern crate authy;
+extern crate serde_json;
+
+#[cfg(test)]
+mod replay {
+    use std::cell::Cell;
+    use std::env;
+    use std::fs;
+    use std::sync::Arc;
+
+    use serde_json::Value;
+
+    use super::authy::{AuthyError, MemoryStore, FileStore, ReplayCache, Totp, VerifyOutcome};
+
+    fn cache() -> ReplayCache {
+        ReplayCache::new(Arc::new(MemoryStore::new()))
+    }
+
+    #[test]
+    fn record() {
+        let cache = cache();
+        assert!(!cache.is_used(1, "0000000").unwrap());
+
+        assert!(cache.record(1, "0000000").unwrap());
+        assert!(cache.is_used(1, "0000000").unwrap());
+        assert!(cache.is_used(1, " 0000000\n").unwrap());
+        assert!(!cache.record(1, "0000000").unwrap());
+
+        assert!(!cache.is_used(2, "0000000").unwrap());
+        assert!(!cache.is_used(1, "0000001").unwrap());
+    }
+
+    #[test]
+    fn verify_totp() {
+        let cache = cache();
+        let totp = Totp::from_base32("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ").unwrap();
+        let code = totp.generate_at(1111111109).unwrap();
+
+        assert_eq!(cache.verify(1, &code, || totp.verify_at(&code, 1111111109)), Ok(VerifyOutcome::Valid));
+        assert_eq!(cache.verify(1, &code, || totp.verify_at(&code, 1111111109 + 30)), Ok(VerifyOutcome::Replayed));
+
+        // Rejected tokens aren't remembered.
+        assert_eq!(cache.verify(1, "000000", || totp.verify_at("000000", 1111111109)), Ok(VerifyOutcome::Invalid));
+        assert!(!cache.is_used(1, "000000").unwrap());
+    }
+
+    #[test]
+    fn verify_skips_check() {
+        let cache = cache();
+        cache.record(1, "0000000").unwrap();
+
+        let called = Cell::new(false);
+        let outcome = cache.verify(1, "0000000", || { called.set(true); Ok(VerifyOutcome::Valid) });
+        assert_eq!(outcome, Ok(VerifyOutcome::Replayed));
+        assert!(!called.get());
+    }
+
+    #[test]
+    fn verify_unconfirmed() {
+        let cache = cache();
+        assert_eq!(cache.verify(1, "0000000", || Ok(VerifyOutcome::AcceptedUnconfirmed)), Ok(VerifyOutcome::AcceptedUnconfirmed));
+        assert_eq!(cache.verify(1, "0000000", || Ok(VerifyOutcome::AcceptedUnconfirmed)), Ok(VerifyOutcome::Replayed));
+    }
+
+    #[test]
+    fn verify_error() {
+        let cache = cache();
+        assert_eq!(cache.verify(1, "0000000", || Err(AuthyError::ServiceUnavailable)), Err(AuthyError::ServiceUnavailable));
+        assert!(!cache.is_used(1, "0000000").unwrap());
+    }
+
+    #[test]
+    fn file_backed() {
+        let path = env::temp_dir().join(format!("authy-replay-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        ReplayCache::new(Arc::new(FileStore::new(&path))).record(1, "0000000").unwrap();
+        let cache = ReplayCache::new(Arc::new(FileStore::new(&path)));
+        assert!(cache.is_used(1, "0000000").unwrap());
+
+        // The only entry is keyed by the user and a hash of the token.
+        let stored: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).expect("Stored entries");
+        let keys: Vec<&String> = stored.as_object().expect("Entries").keys().collect();
+        assert_eq!(keys.len(), 1);
+
+        assert!(keys[0].starts_with("authy:replay:1:"), "{}", keys[0]);
+        let hash = keys[0].trim_start_matches("authy:replay:1:");
+        assert_eq!(hash.len(), 64);
+        assert!(hash.bytes().all(|b| b.is_ascii_hexdigit()));
+        assert!(!stored.to_string().contains("0000000"));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
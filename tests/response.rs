@@ -0,0 +1,394 @@
+extern crate authy;
+#[macro_use]
+extern crate serde_json;
+
+#[cfg(test)]
+mod response {
+    use serde_json;
+
+    const API_URL: &str = "https://sandbox-api.authy.com";
+    const API_KEY: &str = "bf12974d70818a08199d17d5e2bae630";
+
+    use super::authy::{Client, AuthyError, Empty};
+    use super::authy::api::user::UserStatus;
+    use super::authy::api::app::Details;
+    use super::authy::api::phone::PhoneStart;
+
+    // A successful response with `payload` under `key`.
+    fn body(key: &str, payload: serde_json::Value) -> Vec<u8> {
+        let mut body = json!({ "success": true, "message": "OK" });
+        body[key] = payload;
+        serde_json::to_vec(&body).unwrap()
+    }
+
+    #[test]
+    fn unknown_fields() {
+        let c = Client::new(API_URL, API_KEY);
+        let value = json!({
+            "app_id": 1234,
+            "name": "Sandbox App 1",
+            "onetouch_enabled": true,
+            "plan": "sandbox",
+            "sms_enabled": true,
+            "white_label": false,
+        });
+
+        let (_, details) = c.decode::<Details>(&body("app", value), Some("app")).expect("Details");
+        assert_eq!(details.name, "Sandbox App 1");
+        assert_eq!(details.extra["white_label"], json!(false));
+    }
+
+    #[test]
+    fn missing_fields_strict() {
+        let c = Client::new(API_URL, API_KEY);
+        let value = json!({
+            "authy_id": 1234,
+            "confirmed": true,
+            "registered": false,
+            "account_disabled": false,
+            "country_code": 54,
+            "phone_number": "XX-XXX-9302",
+        });
+
+        match c.decode::<UserStatus>(&body("status", value), Some("status")) {
+            Err(AuthyError::JsonParseError(_)) => (),
+            o => unreachable!("Expecting AuthyError::JsonParseError, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn missing_fields_lenient() {
+        let mut c = Client::new(API_URL, API_KEY);
+        c.lenient = true;
+        let value = json!({
+            "authy_id": 1234,
+            "confirmed": true,
+            "registered": false,
+            "account_disabled": false,
+            "country_code": 54,
+            "phone_number": "XX-XXX-9302",
+            "devices": null,
+        });
+
+        let (_, status) = c.decode::<UserStatus>(&body("status", value), Some("status")).expect("UserStatus");
+        assert_eq!(status.id, 1234);
+        assert!(!status.has_hard_token);
+        assert!(status.devices.is_empty());
+    }
+
+    #[test]
+    fn missing_required_lenient() {
+        let mut c = Client::new(API_URL, API_KEY);
+        c.lenient = true;
+        let value = json!({
+            "confirmed": true,
+        });
+
+        match c.decode::<UserStatus>(&body("status", value), Some("status")) {
+            Err(AuthyError::JsonParseError(message)) => assert_eq!(message, "missing field `authy_id`"),
+            o => unreachable!("Expecting AuthyError::JsonParseError, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn decode() {
+        let c = Client::new(API_URL, API_KEY);
+        let body = include_bytes!("fixtures/user_status.json");
+
+        let (status, user) = c.decode::<UserStatus>(body, Some("status")).expect("UserStatus");
+        assert!(status.success);
+        assert_eq!(status.message, "User status.");
+        assert_eq!(user.id, 1234567);
+    }
+
+    #[test]
+    fn decode_empty() {
+        let c = Client::new(API_URL, API_KEY);
+        let body = include_bytes!("fixtures/verify.json");
+
+        let (status, _) = c.decode::<Empty>(body, None).expect("Status");
+        assert!(status.success);
+    }
+
+    #[test]
+    fn decode_whole_body() {
+        let c = Client::new(API_URL, API_KEY);
+        let body = include_bytes!("fixtures/phone_start.json");
+
+        let (status, start) = c.decode::<PhoneStart>(body, None).expect("PhoneStart");
+        assert!(status.success);
+        assert_eq!(status.message, "Text message sent to +1 555-555-5555.");
+        assert!(start.success);
+        assert_eq!(start.message, status.message);
+        assert_eq!(start.seconds_to_expire, 599);
+        assert!(start.extra.is_empty());
+
+        // Escaped field names are still recognized.
+        let body = br#"{"succ\u0065ss": "false", "error_code": "60023", "token": "is invalid"}"#;
+        let (status, value) = c.decode::<serde_json::Value>(body, None).expect("Value");
+        assert!(!status.success);
+        assert_eq!(status.error_code, Some("60023".into()));
+        assert_eq!(value["success"], "false");
+        assert_eq!(value["token"], "is invalid");
+    }
+
+    #[test]
+    fn decode_missing_key() {
+        let c = Client::new(API_URL, API_KEY);
+        let body = include_bytes!("fixtures/user_new.json");
+
+        match c.decode::<UserStatus>(body, Some("status")) {
+            Err(AuthyError::JsonParseError(_)) => (),
+            o => unreachable!("Expecting AuthyError::JsonParseError, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn decode_not_json() {
+        let c = Client::new(API_URL, API_KEY);
+
+        match c.decode::<Empty>(b"<html>Service Unavailable</html>", None) {
+            Err(AuthyError::InvalidServerResponse) => (),
+            o => unreachable!("Expecting AuthyError::InvalidServerResponse, got: {:?}", o),
+        };
+    }
+}
+
+#[cfg(test)]
+mod corpus {
+    use serde_json::{self, Value};
+
+    use super::authy::{Status, AuthyError};
+    use super::authy::api::app::{Details, Stats};
+    use super::authy::api::onetouch::{ApprovalRequest, ApprovalStatus};
+    use super::authy::api::phone::{PhoneInfo, PhoneStart, PhoneVerificationStatus, VerificationStatus};
+    use super::authy::api::user::{PhoneCall, QrCode, UserNew, UserStatus};
+
+    fn load(body: &str) -> (Status, Value) {
+        let value: Value = serde_json::from_str(body).expect("Valid json");
+        let status = serde_json::from_value(value.clone()).expect("Status");
+        (status, value)
+    }
+
+    #[test]
+    fn user_new() {
+        let (status, res) = load(include_str!("fixtures/user_new.json"));
+        assert!(status.success);
+
+        let user: UserNew = serde_json::from_value(res["user"].clone()).expect("UserNew");
+        assert_eq!(user.id, 1234567);
+    }
+
+    #[test]
+    fn user_status() {
+        let (status, res) = load(include_str!("fixtures/user_status.json"));
+        assert!(status.success);
+
+        let user: UserStatus = serde_json::from_value(res["status"].clone()).expect("UserStatus");
+        assert_eq!(user.id, 1234567);
+        assert!(user.confirmed);
+        assert_eq!(user.devices, vec!["iphone", "authy_chrome"]);
+    }
+
+    #[test]
+    fn user_status_coerced() {
+        let (status, res) = load(include_str!("fixtures/user_status_coerced.json"));
+        assert!(status.success);
+
+        let user: UserStatus = serde_json::from_value(res["status"].clone()).expect("UserStatus");
+        assert_eq!(user.id, 1234567);
+        assert_eq!(user.country_code, 54);
+        assert!(!user.confirmed);
+        assert!(!user.registered);
+        assert!(!user.has_hard_token);
+        assert!(user.devices.is_empty());
+    }
+
+    #[test]
+    fn sms() {
+        let (status, res) = load(include_str!("fixtures/sms.json"));
+        assert!(status.success);
+
+        let sms: PhoneCall = serde_json::from_value(res).expect("PhoneCall");
+        assert_eq!(sms.cellphone, "+1-XXX-XXX-XX34");
+        assert_eq!(sms.ignored, None);
+    }
+
+    #[test]
+    fn sms_ignored() {
+        let (status, res) = load(include_str!("fixtures/sms_ignored.json"));
+        assert!(status.success);
+
+        let sms: PhoneCall = serde_json::from_value(res).expect("PhoneCall");
+        assert_eq!(sms.device, Some("iphone".into()));
+        assert_eq!(sms.ignored, Some(true));
+    }
+
+    #[test]
+    fn qr_code() {
+        let (status, res) = load(include_str!("fixtures/qr_code.json"));
+        assert!(status.success);
+
+        let qr: QrCode = serde_json::from_value(res).expect("QrCode");
+        assert!(qr.qr_code.ends_with(".png"));
+        assert_eq!(qr.label, Some("user@domain.com".into()));
+        assert_eq!(qr.issuer, Some("Sandbox App 1".into()));
+    }
+
+    #[test]
+    fn verify() {
+        let (status, _) = load(include_str!("fixtures/verify.json"));
+        assert!(status.success);
+        assert_eq!(status.message, "Token is valid.");
+    }
+
+    #[test]
+    fn verify_invalid() {
+        let (status, _) = load(include_str!("fixtures/verify_invalid.json"));
+        assert!(!status.success);
+        assert_eq!(status.error_code, Some("60020".into()));
+    }
+
+    #[test]
+    fn check() {
+        let (status, _) = load(include_str!("fixtures/verify.json"));
+        assert_eq!(status.clone().check(), Ok(status));
+
+        let (status, _) = load(include_str!("fixtures/verify_invalid.json"));
+        assert_eq!(status.clone().check(), Err(AuthyError::Unsuccessful(status)));
+    }
+
+    #[test]
+    fn app_details() {
+        let (status, res) = load(include_str!("fixtures/app_details.json"));
+        assert!(status.success);
+
+        let details: Details = serde_json::from_value(res["app"].clone()).expect("Details");
+        assert_eq!(details.app_id, 3);
+        assert!(details.onetouch_enabled);
+        assert!(details.extra.contains_key("phone_calls_enabled"));
+    }
+
+    #[test]
+    fn app_stats() {
+        let (status, res) = load(include_str!("fixtures/app_stats.json"));
+        assert!(status.success);
+
+        let stats: Vec<Stats> = serde_json::from_value(res["stats"].clone()).expect("Stats");
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[1].year, 2018);
+        assert_eq!(stats[1].api_calls_count, 112);
+    }
+
+    #[test]
+    fn phone_info() {
+        let (status, res) = load(include_str!("fixtures/phone_info.json"));
+        assert!(status.success);
+
+        let info: PhoneInfo = serde_json::from_value(res).expect("PhoneInfo");
+        assert_eq!(info.phone_type, "cellphone");
+        assert!(!info.ported);
+    }
+
+    #[test]
+    fn phone_start() {
+        let (status, res) = load(include_str!("fixtures/phone_start.json"));
+        assert!(status.success);
+
+        let start: PhoneStart = serde_json::from_value(res).expect("PhoneStart");
+        assert!(start.is_cellphone);
+        assert_eq!(start.seconds_to_expire, 599);
+    }
+
+    #[test]
+    fn phone_verification_status() {
+        let (status, res) = load(include_str!("fixtures/phone_verification_status.json"));
+        assert!(status.success);
+
+        let verification: PhoneVerificationStatus = serde_json::from_value(res).expect("PhoneVerificationStatus");
+        assert_eq!(verification.status, VerificationStatus::Expired);
+        assert_eq!(verification.seconds_to_expire, 0);
+
+        let status: VerificationStatus = serde_json::from_str(r#""canceled""#).expect("VerificationStatus");
+        assert_eq!(status, VerificationStatus::Unknown);
+    }
+
+    #[test]
+    fn phone_check_wrong_code() {
+        let (status, _) = load(include_str!("fixtures/phone_check_wrong_code.json"));
+        assert!(!status.success);
+
+        let error = AuthyError::UnauthorizedKey(status.clone());
+        assert_eq!(error.status(), Some(&status));
+        assert_eq!(error.error_code(), Some("60022"));
+        assert_eq!(AuthyError::ServiceUnavailable.error_code(), None);
+    }
+
+    #[test]
+    fn approval_request() {
+        let (status, res) = load(include_str!("fixtures/approval_request.json"));
+        assert!(status.success);
+
+        let request: ApprovalRequest = serde_json::from_value(res["approval_request"].clone()).expect("ApprovalRequest");
+        assert_eq!(request.uuid, "550e8400-e29b-41d4-a716-446655440000");
+        assert_eq!(request.status, None);
+    }
+
+    #[test]
+    fn approval_request_pending() {
+        let (status, res) = load(include_str!("fixtures/approval_request_pending.json"));
+        assert!(status.success);
+
+        let request: ApprovalRequest = serde_json::from_value(res["approval_request"].clone()).expect("ApprovalRequest");
+        assert_eq!(request.status, Some(ApprovalStatus::Pending));
+        assert_eq!(request.authy_id, Some(1234567));
+        assert_eq!(request.processed_at, None);
+        assert_eq!(request.device, None);
+    }
+
+    #[test]
+    fn approval_request_approved() {
+        let (status, res) = load(include_str!("fixtures/approval_request_approved.json"));
+        assert!(status.success);
+
+        let request: ApprovalRequest = serde_json::from_value(res["approval_request"].clone()).expect("ApprovalRequest");
+        assert_eq!(request.status, Some(ApprovalStatus::Approved));
+        assert_eq!(request.notified, Some(true));
+        assert_eq!(request.processed_at, Some("2018-08-22T10:35:02Z".into()));
+
+        let device = request.device.expect("ApprovalDevice");
+        assert_eq!(device.os_type, Some("android".into()));
+        assert_eq!(device.registration_date, Some(1490996931));
+    }
+
+    #[test]
+    fn approval_request_lenient() {
+        let request: ApprovalRequest = serde_json::from_str(r#"{
+            "uuid": "550e8400-e29b-41d4-a716-446655440000",
+            "status": "snoozed",
+            "_authy_id": "1234567",
+            "seconds_to_expire": null,
+            "device": {"id": "42", "registration_date": "1490996931", "last_sync_date": null}
+        }"#).expect("ApprovalRequest");
+        assert_eq!(request.status, Some(ApprovalStatus::Unknown));
+        assert_eq!(request.authy_id, Some(1234567));
+        assert_eq!(request.seconds_to_expire, None);
+
+        let device = request.device.expect("ApprovalDevice");
+        assert_eq!(device.id, Some(42));
+        assert_eq!(device.registration_date, Some(1490996931));
+        assert_eq!(device.last_sync_date, None);
+    }
+
+    #[test]
+    fn bad_bool() {
+        let res: Result<Status, _> = serde_json::from_str(r#"{"success": "maybe", "message": ""}"#);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn bad_number() {
+        let res: Result<UserNew, _> = serde_json::from_str(r#"{"id": "-1"}"#);
+        assert!(res.is_err());
+    }
+}
@@ -0,0 +1,157 @@
+extern crate authy;
+extern crate serde_json;
+
+#[cfg(test)]
+mod session {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use serde_json;
+
+    use super::authy::{AuthyError, Phone, PhoneStart, ContactType, PhoneCheckOutcome};
+    use super::authy::{VerificationSession, SessionConfig};
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_500_000_000 + seconds)
+    }
+
+    fn phone_start() -> PhoneStart {
+        PhoneStart {
+            seconds_to_expire: 600,
+            uuid: Some("a1b2c3".into()),
+            success: true,
+            ..PhoneStart::default()
+        }
+    }
+
+    fn session() -> VerificationSession {
+        let phone = Phone { country_code: 54, phone_number: "317-338-9302".into(), ..Phone::default() };
+        VerificationSession::new(phone, SessionConfig::default())
+    }
+
+    #[test]
+    fn cooldown() {
+        let mut s = session();
+        assert_eq!(s.ready_to_send_at(at(0)), Ok(ContactType::SMS));
+
+        s.sent_at(ContactType::SMS, &phone_start(), at(0));
+        assert_eq!(s.uuid, Some("a1b2c3".into()));
+        assert_eq!(s.ready_to_send_at(at(10)), Err(AuthyError::ResendCooldown(Duration::from_secs(20))));
+        assert_eq!(s.time_until_resend_at(at(10)), Duration::from_secs(20));
+        assert_eq!(s.ready_to_send_at(at(30)), Ok(ContactType::SMS));
+    }
+
+    #[test]
+    fn cooldown_partial_second() {
+        let mut s = session();
+        s.sent_at(ContactType::SMS, &phone_start(), at(0) + Duration::from_millis(700));
+
+        assert_eq!(s.time_until_resend_at(at(30)), Duration::from_millis(700));
+        assert_eq!(s.ready_to_send_at(at(30)), Err(AuthyError::ResendCooldown(Duration::from_millis(700))));
+        assert_eq!(s.remaining_at(at(600)), Some(Duration::from_millis(700)));
+    }
+
+    #[test]
+    fn switch_to_call() {
+        let mut s = session();
+        s.sent_at(ContactType::SMS, &phone_start(), at(0));
+        s.sent_at(ContactType::SMS, &phone_start(), at(30));
+        assert_eq!(s.next_channel(), ContactType::Call);
+        assert_eq!(s.ready_to_send_at(at(60)), Ok(ContactType::Call));
+
+        let mut s = session();
+        s.config.call_after_sms = None;
+        s.sent_at(ContactType::SMS, &phone_start(), at(0));
+        s.sent_at(ContactType::SMS, &phone_start(), at(30));
+        assert_eq!(s.next_channel(), ContactType::SMS);
+    }
+
+    #[test]
+    fn max_sends() {
+        let mut s = session();
+        for i in 0..5 {
+            let via = s.ready_to_send_at(at(i * 30)).expect("Ready to send");
+            s.sent_at(via, &phone_start(), at(i * 30));
+        }
+        assert_eq!(s.sends(), 5);
+
+        match s.ready_to_send_at(at(1000)) {
+            Err(AuthyError::AttemptsExceeded(_)) => (),
+            o => unreachable!("Expecting AuthyError::AttemptsExceeded, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn max_attempts() {
+        let mut s = session();
+        s.sent_at(ContactType::SMS, &phone_start(), at(0));
+        for _ in 0..5 {
+            assert_eq!(s.ready_to_check_at(at(10)), Ok(None));
+            s.checked(PhoneCheckOutcome::WrongCode);
+        }
+        assert_eq!(s.attempts_left(), 0);
+
+        match s.ready_to_check_at(at(10)) {
+            Err(AuthyError::AttemptsExceeded(_)) => (),
+            o => unreachable!("Expecting AuthyError::AttemptsExceeded, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn expiry() {
+        let mut s = session();
+        assert_eq!(s.ready_to_check_at(at(0)), Ok(Some(PhoneCheckOutcome::NoPendingVerification)));
+        assert_eq!(s.remaining_at(at(0)), None);
+
+        s.sent_at(ContactType::SMS, &phone_start(), at(0));
+        assert_eq!(s.remaining_at(at(100)), Some(Duration::from_secs(500)));
+        assert!(!s.is_expired_at(at(100)));
+
+        assert_eq!(s.remaining_at(at(600)), None);
+        assert!(s.is_expired_at(at(600)));
+        assert_eq!(s.ready_to_check_at(at(600)), Ok(Some(PhoneCheckOutcome::Expired)));
+    }
+
+    #[test]
+    fn verified() {
+        let mut s = session();
+        s.sent_at(ContactType::SMS, &phone_start(), at(0));
+        s.checked(PhoneCheckOutcome::Verified);
+
+        assert!(s.is_verified());
+        assert!(!s.is_expired_at(at(1000)));
+        assert_eq!(s.ready_to_check_at(at(10)), Ok(Some(PhoneCheckOutcome::Verified)));
+        match s.ready_to_send_at(at(1000)) {
+            Err(AuthyError::InvalidRequest(_)) => (),
+            o => unreachable!("Expecting AuthyError::InvalidRequest, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn serialize() {
+        let mut s = session();
+        s.sent_at(ContactType::SMS, &phone_start(), at(0));
+        s.checked(PhoneCheckOutcome::WrongCode);
+
+        let json = serde_json::to_string(&s).expect("Serialized");
+        assert!(!json.contains("max_attempts"));
+
+        let restored: VerificationSession = serde_json::from_str(&json).expect("VerificationSession");
+        let restored = restored.config(SessionConfig::default());
+        assert_eq!(restored, s);
+        assert_eq!(restored.attempts_left(), 4);
+        assert_eq!(restored.time_until_resend_at(at(10)), Duration::from_secs(20));
+    }
+
+    #[test]
+    fn serialize_config_ignored() {
+        let mut s = session();
+        s.sent_at(ContactType::SMS, &phone_start(), at(0));
+
+        let mut json = serde_json::to_value(&s).expect("Serialized");
+        json["config"] = serde_json::to_value(SessionConfig { max_attempts: 1000, ..SessionConfig::default() }).expect("Serialized");
+
+        let restored: VerificationSession = serde_json::from_value(json).expect("VerificationSession");
+        let config = SessionConfig { max_attempts: 3, ..SessionConfig::default() };
+        assert_eq!(restored.config(config).attempts_left(), 3);
+    }
+}
@@ -0,0 +1,77 @@
+extern crate authy;
+
+#[cfg(test)]
+mod store {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use super::authy::{AuthyError, Store, MemoryStore, FileStore};
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("authy-store-{}-{}.json", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn exercise(store: &dyn Store) {
+        assert_eq!(store.get("a").unwrap(), None);
+
+        store.set("a", "1", None).unwrap();
+        assert_eq!(store.get("a").unwrap(), Some("1".into()));
+
+        assert!(!store.add("a", "2", None).unwrap());
+        assert_eq!(store.get("a").unwrap(), Some("1".into()));
+        assert!(store.add("b", "2", Some(Duration::from_secs(60))).unwrap());
+        assert_eq!(store.get("b").unwrap(), Some("2".into()));
+
+        assert_eq!(store.increment("count", Some(Duration::from_secs(60))).unwrap(), 1);
+        assert_eq!(store.increment("count", Some(Duration::from_secs(60))).unwrap(), 2);
+        assert_eq!(store.increment("a", None).unwrap(), 2);
+
+        store.remove("a").unwrap();
+        assert_eq!(store.get("a").unwrap(), None);
+        assert!(store.add("a", "3", None).unwrap());
+    }
+
+    #[test]
+    fn memory() {
+        exercise(&MemoryStore::new());
+    }
+
+    #[test]
+    fn file() {
+        let path = temp_path("file");
+        exercise(&FileStore::new(&path));
+
+        // A new store reads what the first one wrote.
+        let store = FileStore::new(&path);
+        assert_eq!(store.get("a").unwrap(), Some("3".into()));
+        assert_eq!(store.get("count").unwrap(), Some("2".into()));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn increment_not_a_counter() {
+        let store = MemoryStore::new();
+        store.set("a", "x", None).unwrap();
+        match store.increment("a", None) {
+            Err(AuthyError::InvalidRequest(_)) => (),
+            o => unreachable!("Expecting AuthyError::InvalidRequest, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn file_corrupt() {
+        let path = temp_path("corrupt");
+        fs::write(&path, "not json").unwrap();
+        match FileStore::new(&path).get("a") {
+            Err(AuthyError::JsonParseError(_)) => (),
+            o => unreachable!("Expecting AuthyError::JsonParseError, got: {:?}", o),
+        };
+
+        fs::remove_file(&path).unwrap();
+    }
+}
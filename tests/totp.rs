@@ -0,0 +1,118 @@
+extern crate authy;
+
+#[cfg(test)]
+mod totp {
+    use super::authy::{AuthyError, Totp, Algorithm, VerifyOutcome};
+    use super::authy::totp::MAX_SKEW;
+
+    // RFC 6238, appendix B.
+    const VECTORS: &[(u64, &str, &str, &str)] = &[
+        (59, "94287082", "46119246", "90693936"),
+        (1111111109, "07081804", "68084774", "25091201"),
+        (1111111111, "14050471", "67062674", "99943326"),
+        (1234567890, "89005924", "91819424", "93441116"),
+        (2000000000, "69279037", "90698825", "38618901"),
+        (20000000000, "65353130", "77737706", "47863826"),
+    ];
+
+    fn rfc(algorithm: Algorithm) -> Totp {
+        let secret: &[u8] = match algorithm {
+            Algorithm::SHA1 => b"12345678901234567890",
+            Algorithm::SHA256 => b"12345678901234567890123456789012",
+            Algorithm::SHA512 => b"1234567890123456789012345678901234567890123456789012345678901234",
+        };
+        Totp::new(secret).algorithm(algorithm).digits(8)
+    }
+
+    #[test]
+    fn rfc_vectors() {
+        for &(time, sha1, sha256, sha512) in VECTORS {
+            assert_eq!(rfc(Algorithm::SHA1).generate_at(time).unwrap(), sha1, "SHA1 at {}", time);
+            assert_eq!(rfc(Algorithm::SHA256).generate_at(time).unwrap(), sha256, "SHA256 at {}", time);
+            assert_eq!(rfc(Algorithm::SHA512).generate_at(time).unwrap(), sha512, "SHA512 at {}", time);
+        }
+    }
+
+    #[test]
+    fn verify_rfc_vectors() {
+        for &(time, sha1, sha256, sha512) in VECTORS {
+            assert_eq!(rfc(Algorithm::SHA1).verify_at(sha1, time), Ok(VerifyOutcome::Valid));
+            assert_eq!(rfc(Algorithm::SHA256).verify_at(sha256, time), Ok(VerifyOutcome::Valid));
+            assert_eq!(rfc(Algorithm::SHA512).verify_at(sha512, time), Ok(VerifyOutcome::Valid));
+            assert_eq!(rfc(Algorithm::SHA256).verify_at(sha1, time), Ok(VerifyOutcome::Invalid));
+        }
+    }
+
+    #[test]
+    fn skew() {
+        let totp = rfc(Algorithm::SHA1);
+        let code = totp.generate_at(1111111109).unwrap();
+
+        assert_eq!(totp.verify_at(&code, 1111111109 - 30), Ok(VerifyOutcome::Valid));
+        assert_eq!(totp.verify_at(&code, 1111111109 + 30), Ok(VerifyOutcome::Valid));
+        assert_eq!(totp.verify_at(&code, 1111111109 + 60), Ok(VerifyOutcome::Invalid));
+
+        let totp = totp.skew(0);
+        assert_eq!(totp.verify_at(&code, 1111111109 + 30), Ok(VerifyOutcome::Invalid));
+        let totp = totp.skew(2);
+        assert_eq!(totp.verify_at(&code, 1111111109 + 60), Ok(VerifyOutcome::Valid));
+    }
+
+    #[test]
+    fn digits_and_period() {
+        let totp = rfc(Algorithm::SHA1).digits(6);
+        assert_eq!(totp.generate_at(59).unwrap(), "287082");
+
+        // With a 60 second period, 59 is in the first period rather than the second.
+        let totp = rfc(Algorithm::SHA1).period(60);
+        assert_eq!(totp.generate_at(59).unwrap(), rfc(Algorithm::SHA1).generate_at(0).unwrap());
+    }
+
+    #[test]
+    fn verify_ignores_whitespace() {
+        let totp = rfc(Algorithm::SHA1);
+        assert_eq!(totp.verify_at(" 94287082\n", 59), Ok(VerifyOutcome::Valid));
+        assert_eq!(totp.verify_at("9428708", 59), Ok(VerifyOutcome::Invalid));
+        assert_eq!(totp.verify_at("", 59), Ok(VerifyOutcome::Invalid));
+    }
+
+    #[test]
+    fn from_base32() {
+        let totp = Totp::from_base32("gezd gnbv gy3t qojq gezd gnbv gy3t qojq").unwrap();
+        assert_eq!(totp.secret, b"12345678901234567890".to_vec());
+
+        match Totp::from_base32("GEZDGNBV1") {
+            Err(AuthyError::InvalidRequest(_)) => (),
+            o => unreachable!("Expecting AuthyError::InvalidRequest, got: {:?}", o),
+        };
+    }
+
+    #[test]
+    fn debug_redacts_secret() {
+        let debug = format!("{:?}", Totp::new(b"a very secret key"));
+        assert!(!debug.contains("secret key"), "{}", debug);
+        assert!(!debug.contains("97"), "{}", debug);
+        assert!(debug.contains("SHA1"), "{}", debug);
+    }
+
+    #[test]
+    fn validate() {
+        for totp in &[Totp::new(b""), rfc(Algorithm::SHA1).digits(5), rfc(Algorithm::SHA1).digits(11), rfc(Algorithm::SHA1).period(0)] {
+            match totp.generate_at(59) {
+                Err(AuthyError::InvalidRequest(_)) => (),
+                o => unreachable!("Expecting AuthyError::InvalidRequest, got: {:?}", o),
+            };
+        }
+    }
+
+    #[test]
+    fn validate_skew() {
+        assert_eq!(rfc(Algorithm::SHA1).skew(MAX_SKEW).validate(), Ok(()));
+
+        let totp = rfc(Algorithm::SHA1).skew(u32::MAX);
+        match totp.verify_at("94287082", 59) {
+            Err(AuthyError::InvalidRequest(message)) => assert_eq!(message, format!("Skew must be at most {}", MAX_SKEW)),
+            o => unreachable!("Expecting AuthyError::InvalidRequest, got: {:?}", o),
+        };
+    }
+}
@@ -0,0 +1,35 @@
+extern crate authy;
+
+#[cfg(test)]
+mod user {
+    const API_URL: &str = "https://sandbox-api.authy.com";
+    const API_KEY: &str = "bf12974d70818a08199d17d5e2bae630";
+
+    use super::authy::{Client, User, VerifyOptions, VerifyOutcome};
+
+    #[test]
+    fn verify_unconfirmed() {
+        let mut c = Client::new(API_URL, API_KEY);
+        c.retry_wait = 3000;
+        c.retry_count = 10;
+        let mut user = User::create(&c, "user@domain.com", 54, "317-338-9302", false).expect("User to be created");
+
+        assert!(!user.confirmed);
+        let outcome = user.verify(&c, "0000000", &VerifyOptions::default()).expect("VerifyOutcome");
+        assert_eq!(outcome, VerifyOutcome::AcceptedUnconfirmed);
+        assert!(!outcome.is_valid());
+    }
+
+    #[test]
+    fn verify_invalid() {
+        let mut c = Client::new(API_URL, API_KEY);
+        c.retry_wait = 3000;
+        c.retry_count = 10;
+        let mut user = User::create(&c, "user@domain.com", 54, "317-338-9302", false).expect("User to be created");
+
+        let options = VerifyOptions { force: true, ..VerifyOptions::default() };
+        let outcome = user.verify(&c, "123456", &options).expect("VerifyOutcome");
+        assert_eq!(outcome, VerifyOutcome::Invalid);
+        assert!(!outcome.is_valid());
+    }
+}